@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS admin_users (
+  id serial PRIMARY KEY,
+  username TEXT NOT NULL UNIQUE,
+  password_hash TEXT NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
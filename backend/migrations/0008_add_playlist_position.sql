@@ -0,0 +1,12 @@
+ALTER TABLE current_playlist
+ADD COLUMN position INTEGER;
+
+-- Keep the current order of the queue
+UPDATE current_playlist
+SET position = ordered.rank
+FROM (
+  SELECT id, ROW_NUMBER() OVER (ORDER BY created_at ASC, id ASC) AS rank
+  FROM current_playlist
+  WHERE is_deleted IS NOT TRUE
+) AS ordered
+WHERE current_playlist.id = ordered.id;
@@ -0,0 +1,12 @@
+-- Lifecycle of a queue entry : queued -> playing -> sung / skipped, or removed by the maestro
+ALTER TABLE current_playlist
+ADD COLUMN status TEXT NOT NULL DEFAULT 'queued',
+ADD COLUMN started_at TIMESTAMP,
+ADD COLUMN ended_at TIMESTAMP;
+
+UPDATE current_playlist
+SET status = 'removed'
+WHERE is_deleted IS TRUE;
+
+ALTER TABLE current_playlist
+DROP COLUMN is_deleted;
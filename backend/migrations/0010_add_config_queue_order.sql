@@ -0,0 +1,6 @@
+-- 'fifo' or 'round_robin'
+INSERT INTO config (id, name, value)
+VALUES
+  (4, 'queue_order', 'fifo')
+ON CONFLICT (id)
+DO NOTHING;
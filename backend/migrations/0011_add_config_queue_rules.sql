@@ -0,0 +1,8 @@
+-- Limits applied when a guest adds a song, 0 disables the numeric ones
+INSERT INTO config (id, name, value)
+VALUES
+  (5, 'max_songs_per_singer', '0'),
+  (6, 'reject_duplicate_songs', 'no'),
+  (7, 'song_cooldown_minutes', '0')
+ON CONFLICT (id)
+DO NOTHING;
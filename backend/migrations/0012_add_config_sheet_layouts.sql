@@ -0,0 +1,5 @@
+-- Column mapping and range of each Google Sheet, JSON object keyed by sheet id
+INSERT INTO config (id, name, value)
+VALUES (8, 'google_sheet_layouts', '{}')
+ON CONFLICT (id)
+DO NOTHING;
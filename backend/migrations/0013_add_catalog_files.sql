@@ -0,0 +1,13 @@
+-- Song catalogs uploaded from the admin page, the last one is used by the 'local_file' source
+CREATE TABLE IF NOT EXISTS catalog_files (
+  id serial PRIMARY KEY,
+  filename TEXT,
+  format TEXT NOT NULL,
+  content TEXT NOT NULL,
+  uploaded_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO config (id, name, value)
+VALUES (9, 'catalog_source', 'google_sheet')
+ON CONFLICT (id)
+DO NOTHING;
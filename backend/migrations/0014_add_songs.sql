@@ -0,0 +1,16 @@
+-- Each refresh of the song catalog, from Google Sheets or an uploaded file
+CREATE TABLE IF NOT EXISTS catalog_imports (
+  id serial PRIMARY KEY,
+  source TEXT NOT NULL,
+  song_count INTEGER NOT NULL,
+  imported_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Song catalog of the last import, loaded in memory at startup
+CREATE TABLE IF NOT EXISTS songs (
+  id INTEGER PRIMARY KEY,
+  artist TEXT NOT NULL,
+  title TEXT NOT NULL,
+  lyrics_url TEXT NOT NULL DEFAULT '',
+  import_id INTEGER REFERENCES catalog_imports(id)
+);
@@ -0,0 +1,30 @@
+-- Catalog songs keep their id across imports : they are matched on a normalized artist + title key
+-- and the ones missing from the last import are only deactivated
+ALTER TABLE songs
+ADD COLUMN song_key TEXT UNIQUE,
+ADD COLUMN active BOOLEAN NOT NULL DEFAULT TRUE;
+
+CREATE SEQUENCE IF NOT EXISTS songs_id_seq OWNED BY songs.id;
+SELECT setval('songs_id_seq', COALESCE((SELECT MAX(id) FROM songs), 0) + 1, false);
+ALTER TABLE songs ALTER COLUMN id SET DEFAULT nextval('songs_id_seq');
+
+-- Queue entries reference the catalog instead of copying the song
+ALTER TABLE current_playlist
+ADD COLUMN song_id INTEGER REFERENCES songs(id);
+
+-- Songs of past entries missing from the catalog are kept as inactive songs
+INSERT INTO songs (artist, title, lyrics_url, active)
+SELECT DISTINCT ON (LOWER(TRIM(p.artist)), LOWER(TRIM(p.title))) p.artist, p.title, COALESCE(p.lyrics_url, ''), FALSE
+FROM current_playlist p
+WHERE NOT EXISTS (SELECT 1 FROM songs s WHERE LOWER(TRIM(s.artist)) = LOWER(TRIM(p.artist)) AND LOWER(TRIM(s.title)) = LOWER(TRIM(p.title)));
+
+UPDATE current_playlist p
+SET song_id = (SELECT s.id FROM songs s
+               WHERE LOWER(TRIM(s.artist)) = LOWER(TRIM(p.artist)) AND LOWER(TRIM(s.title)) = LOWER(TRIM(p.title))
+               ORDER BY s.active DESC, s.id ASC LIMIT 1);
+
+ALTER TABLE current_playlist
+ALTER COLUMN song_id SET NOT NULL,
+DROP COLUMN artist,
+DROP COLUMN title,
+DROP COLUMN lyrics_url;
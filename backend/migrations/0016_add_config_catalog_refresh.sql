@@ -0,0 +1,5 @@
+-- Minutes between two automatic refreshes of the song catalog, 0 disables them
+INSERT INTO config (id, name, value)
+VALUES (10, 'catalog_refresh_minutes', '0')
+ON CONFLICT (id)
+DO NOTHING;
@@ -0,0 +1,2 @@
+-- Sheet, tab or file each catalog song was imported from, several sheets can be merged
+ALTER TABLE songs ADD COLUMN source TEXT NOT NULL DEFAULT '';
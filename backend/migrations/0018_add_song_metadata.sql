@@ -0,0 +1,8 @@
+-- Optional details of the catalog songs, read from extra columns of the sheet
+ALTER TABLE songs
+    ADD COLUMN genre TEXT,
+    ADD COLUMN language TEXT,
+    ADD COLUMN year INTEGER,
+    ADD COLUMN duet BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN difficulty TEXT,
+    ADD COLUMN duration INTEGER; -- seconds
@@ -0,0 +1,7 @@
+-- Lyrics kept on the server, plain text or LRC with timestamps, shown instead of the external link
+CREATE TABLE IF NOT EXISTS song_lyrics (
+  song_id INTEGER PRIMARY KEY REFERENCES songs(id) ON DELETE CASCADE,
+  format TEXT NOT NULL,
+  content TEXT NOT NULL,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
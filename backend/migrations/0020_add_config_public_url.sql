@@ -0,0 +1,5 @@
+-- Address guests open to join, encoded in the QR code. Empty to use the address of the server
+INSERT INTO config (id, name, value)
+VALUES (11, 'public_url', '')
+ON CONFLICT (id)
+DO NOTHING;
@@ -0,0 +1,6 @@
+-- Moderation of the suggestions : new -> handled or rejected by the maestro
+ALTER TABLE suggestions
+ADD COLUMN status TEXT NOT NULL DEFAULT 'new',
+ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS suggestions_status_idx ON suggestions (status, created_at DESC);
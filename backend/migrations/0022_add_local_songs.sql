@@ -0,0 +1,11 @@
+-- Songs added by the maestro, from a suggestion, on top of the imported catalog.
+-- They are added again after each import so a refresh never deactivates them
+CREATE TABLE IF NOT EXISTS local_songs (
+  id serial PRIMARY KEY,
+  song_key TEXT NOT NULL UNIQUE,
+  artist TEXT NOT NULL,
+  title TEXT NOT NULL,
+  lyrics_url TEXT NOT NULL DEFAULT '',
+  suggestion_id INTEGER REFERENCES suggestions(id) ON DELETE SET NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,10 @@
+-- Structured suggestions : the same song asked twice is one suggestion with one more vote
+ALTER TABLE suggestions
+ADD COLUMN artist TEXT NOT NULL DEFAULT '',
+ADD COLUMN title TEXT NOT NULL DEFAULT '',
+ADD COLUMN suggestion_key TEXT,
+ADD COLUMN votes INTEGER NOT NULL DEFAULT 1;
+
+-- NULL for the free text suggestions, they are never grouped
+CREATE UNIQUE INDEX IF NOT EXISTS suggestions_key_idx ON suggestions (suggestion_key);
+CREATE INDEX IF NOT EXISTS suggestions_votes_idx ON suggestions (votes DESC, created_at DESC);
@@ -0,0 +1,5 @@
+-- Words refused in the names and suggestions of the guests, separated by commas. Empty to refuse none
+INSERT INTO config (id, name, value)
+VALUES (12, 'banned_words', '')
+ON CONFLICT (id)
+DO NOTHING;
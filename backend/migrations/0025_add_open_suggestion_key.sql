@@ -0,0 +1,3 @@
+-- Only the open suggestions group the same song, a song asked again after being rejected or added is a new suggestion
+DROP INDEX IF EXISTS suggestions_key_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS suggestions_open_key_idx ON suggestions (suggestion_key) WHERE status IN ('new', 'handled');
@@ -0,0 +1,12 @@
+-- One vote per guest and per suggestion, the votes column is the count of this table
+CREATE TABLE IF NOT EXISTS suggestion_votes (
+    suggestion_id INTEGER NOT NULL REFERENCES suggestions(id) ON DELETE CASCADE,
+    client_id TEXT NOT NULL,
+    UNIQUE (suggestion_id, client_id)
+);
+
+-- The guests of the votes already counted are unknown, each one gets its own voter
+INSERT INTO suggestion_votes (suggestion_id, client_id)
+SELECT id, 'before voters ' || vote
+FROM suggestions, generate_series(1, suggestions.votes) AS vote
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+use actix_web::{web, post, get, Responder, HttpResponse, Error};
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::middleware::{from_fn, Next};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::password_hash::SaltString;
+use argon2::password_hash::rand_core::OsRng;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use sqlx::{FromRow, PgPool};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde_json::json;
+use log::{info, warn};
+
+use crate::state::AppState;
+
+
+// Admin sessions last for one evening
+const TOKEN_LIFETIME_SECONDS: usize = 12 * 3600;
+
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, FromRow, Debug)]
+pub struct AdminUser {
+    pub id: i32,
+    pub username: String,
+    pub password_hash: String,
+}
+
+// JWT claims of an admin session
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Claims {
+    pub sub: String,   // Admin username
+    pub exp: usize,    // Expiration time
+    pub iat: usize,    // Issued at
+}
+
+
+#[post("/login")]
+async fn login(credentials: web::Json<Credentials>, state: web::Data<AppState>) -> impl Responder {
+
+    let credentials = credentials.into_inner();
+    let secret = state.jwt_secret.clone();
+    let admin = AdminUser::get_from_username(&credentials.username, state).await;
+
+    let admin = match admin {
+        Ok(Some(admin)) if verify_password(&credentials.password, &admin.password_hash) => admin,
+        Ok(_) => return HttpResponse::Unauthorized().json(json!({
+            "status": "ko",
+            "content": "invalid username or password",
+        })),
+        Err(error) => return HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    };
+
+    let secret = match secret {
+        Some(secret) => secret,
+        None => return HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": "JWT_SECRET is not set",
+        })),
+    };
+
+    match create_token(&admin.username, &secret) {
+        Ok((token, expires_at)) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": {
+                "token": token,
+                "expires_at": expires_at,
+            },
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+// Only reachable with a valid token, lets the frontend check a stored session
+#[get("/auth-check", wrap = "from_fn(require_admin)")]
+async fn auth_check() -> impl Responder {
+    HttpResponse::Ok().json(json!({
+        "status": "ok",
+        "content": "authenticated",
+    }))
+}
+
+
+/// Middleware rejecting every request without a valid admin bearer token
+pub async fn require_admin<B: MessageBody + 'static>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+
+    let token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+
+    let secret = req.app_data::<web::Data<AppState>>().and_then(|state| state.jwt_secret.clone());
+    let is_authorized = match (token, secret) {
+        (Some(token), Some(secret)) => verify_token(&token, &secret).is_ok(),
+        _ => false,
+    };
+
+    if is_authorized {
+        next.call(req).await.map(|res| res.map_into_left_body())
+    } else {
+        let response = HttpResponse::Unauthorized().json(json!({
+            "status": "ko",
+            "content": "authentication required",
+        }));
+        Ok(req.into_response(response).map_into_right_body())
+    }
+}
+
+
+impl AdminUser {
+    pub async fn get_from_username(username: &str, state: web::Data<AppState>) -> Result<Option<AdminUser>, sqlx::Error> {
+        sqlx::query_as("select id, username, password_hash FROM admin_users WHERE username = $1")
+        .bind(username)
+        .fetch_optional(&state.pool)
+        .await
+    }
+}
+
+
+/// Create the admin account from ADMIN_USERNAME / ADMIN_PASSWORD if it does not exist yet
+pub async fn ensure_admin_user(pool: &PgPool) -> Result<(), sqlx::Error> {
+    if jwt_secret().is_none() {
+        warn!("JWT_SECRET is not set, admin login is disabled");
+    }
+
+    let password = match std::env::var("ADMIN_PASSWORD") {
+        Ok(password) if !password.is_empty() => password,
+        _ => return Ok(()),
+    };
+    let username = std::env::var("ADMIN_USERNAME").unwrap_or_else(|_| "maestro".to_string());
+
+    let password_hash = match hash_password(&password) {
+        Ok(hash) => hash,
+        Err(error) => {
+            warn!("Could not hash the admin password: {}", error);
+            return Ok(());
+        }
+    };
+
+    let result = sqlx::query("INSERT INTO admin_users(username, password_hash) VALUES ($1, $2) ON CONFLICT (username) DO NOTHING")
+        .bind(&username)
+        .bind(&password_hash)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() > 0 {
+        info!("Admin user {} created", username);
+    }
+    Ok(())
+}
+
+
+/// Read once at startup, kept in the state
+pub fn jwt_secret() -> Option<String> {
+    std::env::var("JWT_SECRET").ok().filter(|secret| !secret.is_empty())
+}
+
+fn now() -> usize {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as usize
+}
+
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(parsed_hash) => Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Sign a new admin token, returns it with its expiration timestamp
+pub fn create_token(username: &str, secret: &str) -> Result<(String, usize), jsonwebtoken::errors::Error> {
+    let now = now();
+    let claims = Claims {
+        sub: username.to_string(),
+        exp: now + TOKEN_LIFETIME_SECONDS,
+        iat: now,
+    };
+
+    let token = encode(
+        &Header::new(Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )?;
+    Ok((token, claims.exp))
+}
+
+pub fn verify_token(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map(|data| data.claims)
+}
+
+// Secret of the test states, see test_admin_header
+#[cfg(test)]
+pub const TEST_SECRET: &str = "test-secret";
+
+/// Authorization header of an admin session signed with TEST_SECRET, for the tests of the admin routes
+#[cfg(test)]
+pub fn test_admin_header() -> (actix_web::http::header::HeaderName, String) {
+    let (token, _) = create_token("maestro", TEST_SECRET).unwrap();
+    (AUTHORIZATION, format!("Bearer {}", token))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use actix_web::http::StatusCode;
+    use serde::Deserialize;
+    use sqlx::PgPool;
+    use std::fs;
+    use std::path::Path;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    #[allow(non_snake_case)]
+    struct Secrets {
+        pub DATABASE_URL: String,
+    }
+
+    fn load_secrets() -> Secrets {
+        let content = fs::read_to_string(Path::new("Secrets.toml"))
+            .expect("Failed to read Secrets.toml");
+        toml::from_str(&content)
+            .expect("Failed to parse Secrets.toml")
+    }
+
+    #[actix_web::test]
+    async fn test_password_hash() {
+        let hash = hash_password("chanteur").unwrap();
+
+        assert!(verify_password("chanteur", &hash));
+        assert!(!verify_password("chanteuse", &hash));
+        assert!(!verify_password("chanteur", "not a hash"));
+    }
+
+    #[actix_web::test]
+    async fn test_token() {
+        let (token, expires_at) = create_token("maestro", TEST_SECRET).unwrap();
+        let claims = verify_token(&token, TEST_SECRET).unwrap();
+
+        assert_eq!(claims.sub, "maestro");
+        assert_eq!(claims.exp, expires_at);
+        assert!(verify_token(&token, "another-secret").is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_require_admin() {
+        let pool = PgPool::connect_lazy(&load_secrets().DATABASE_URL).unwrap();
+        let state = web::Data::new(AppState { jwt_secret: Some(TEST_SECRET.to_string()), ..AppState::new(pool) });
+        let app = test::init_service(
+            App::new().app_data(state).service(web::scope("/api").service(auth_check))
+        ).await;
+
+        let req = test::TestRequest::get().uri("/api/auth-check").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/auth-check")
+            .insert_header((AUTHORIZATION, "Bearer not-a-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/auth-check")
+            .insert_header(test_admin_header())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
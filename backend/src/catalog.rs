@@ -0,0 +1,740 @@
+use std::collections::HashMap;
+use std::fmt;
+use actix_web::{web, post, http::StatusCode, HttpRequest, Responder, HttpResponse};
+use actix_web::middleware::from_fn;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use log::{debug, warn};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use deunicode::deunicode;
+
+use crate::auth::require_admin;
+use crate::config::get_config_value;
+use crate::catalog_diff::{CatalogDiff, ImportedRows, SkipReason, SkippedRow};
+use crate::google_sheet_response::GoogleError;
+use crate::local_catalog::restore_local_songs;
+use crate::sheet_layout::{self, SheetLayout};
+use crate::song::Song;
+use crate::song_metadata::{MetadataCells, SongMetadata};
+use crate::state::AppState;
+
+
+// Biggest catalog file accepted by the upload
+pub const MAX_CATALOG_FILE_SIZE: usize = 10 * 1024 * 1024;
+
+// Accepted names for the columns of an imported file, the first matching one wins
+const TITLE_COLUMNS: [&str; 3] = ["title", "titre", "chanson"];
+const ARTIST_COLUMNS: [&str; 3] = ["artist", "artiste", "interprète"];
+const LYRICS_COLUMNS: [&str; 3] = ["lyrics_url", "lyrics", "paroles"];
+const GENRE_COLUMNS: [&str; 2] = ["genre", "style"];
+const LANGUAGE_COLUMNS: [&str; 2] = ["language", "langue"];
+const YEAR_COLUMNS: [&str; 3] = ["year", "année", "annee"];
+const DUET_COLUMNS: [&str; 3] = ["duet", "duo", "duos"];
+const DIFFICULTY_COLUMNS: [&str; 3] = ["difficulty", "difficulté", "difficulte"];
+const DURATION_COLUMNS: [&str; 3] = ["duration", "durée", "duree"];
+
+
+/// Where the song catalog comes from, stored in the `catalog_source` config
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CatalogSource {
+    GoogleSheet,
+    /// Last CSV or JSON file uploaded from the admin page, for offline events
+    LocalFile,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FileFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug)]
+pub enum CatalogError {
+    Google(GoogleError),
+    Database(sqlx::Error),
+    InvalidFile(String),
+    NoFile,
+    NoSheet,
+}
+
+impl From<sqlx::Error> for CatalogError {
+    fn from(error: sqlx::Error) -> Self {
+        CatalogError::Database(error)
+    }
+}
+
+impl From<GoogleError> for CatalogError {
+    fn from(error: GoogleError) -> Self {
+        CatalogError::Google(error)
+    }
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Google(error) => write!(f, "{}", error),
+            CatalogError::Database(error) => write!(f, "{}", error),
+            CatalogError::InvalidFile(error) => write!(f, "Invalid catalog file: {}", error),
+            CatalogError::NoFile => write!(f, "No catalog file has been uploaded"),
+            CatalogError::NoSheet => write!(f, "No Google Sheet id is configured"),
+        }
+    }
+}
+
+impl CatalogError {
+    /// Status answered when the catalog can't be loaded, Google failures are reported as a bad gateway
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            CatalogError::Google(error) if error.is_configuration() => StatusCode::INTERNAL_SERVER_ERROR,
+            CatalogError::Google(_) => StatusCode::BAD_GATEWAY,
+            CatalogError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            CatalogError::InvalidFile(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            CatalogError::NoFile | CatalogError::NoSheet => StatusCode::NOT_FOUND,
+        }
+    }
+}
+
+
+/// One song of an imported JSON file, french field names are accepted too
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+struct CatalogEntry {
+    #[serde(alias = "titre")]
+    title: String,
+    #[serde(alias = "artiste")]
+    artist: String,
+    #[serde(default, alias = "lyrics", alias = "paroles")]
+    lyrics_url: Option<String>,
+    // Details written as text or as numbers
+    #[serde(default, alias = "style")]
+    genre: Option<serde_json::Value>,
+    #[serde(default, alias = "langue")]
+    language: Option<serde_json::Value>,
+    #[serde(default, alias = "année", alias = "annee")]
+    year: Option<serde_json::Value>,
+    #[serde(default, alias = "duo")]
+    duet: Option<serde_json::Value>,
+    #[serde(default, alias = "difficulté", alias = "difficulte")]
+    difficulty: Option<serde_json::Value>,
+    #[serde(default, alias = "durée", alias = "duree")]
+    duration: Option<serde_json::Value>,
+}
+
+impl CatalogEntry {
+    fn metadata(&self) -> SongMetadata {
+        let text = |value: &Option<serde_json::Value>| match value {
+            Some(serde_json::Value::String(text)) => Some(text.clone()),
+            Some(serde_json::Value::Null) | None => None,
+            Some(value) => Some(value.to_string()),
+        };
+        SongMetadata::from_cells(MetadataCells {
+            genre: text(&self.genre),
+            language: text(&self.language),
+            year: text(&self.year),
+            duet: text(&self.duet),
+            difficulty: text(&self.difficulty),
+            duration: text(&self.duration),
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct UploadQuery {
+    filename: Option<String>,
+}
+
+
+#[post("/catalog-upload", wrap = "from_fn(require_admin)")]
+async fn catalog_upload(req: HttpRequest, query: web::Query<UploadQuery>, body: String, state: web::Data<AppState>) -> impl Responder {
+
+    let content_type = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let format = FileFormat::detect(content_type, query.filename.as_deref().unwrap_or_default());
+
+    let rows = match parse_catalog_file(&body, format) {
+        Ok(rows) => rows.with_source(&file_source(query.filename.as_deref())),
+        Err(error) => return HttpResponse::BadRequest().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    };
+
+    let saved = sqlx::query("INSERT INTO catalog_files (filename, format, content) VALUES ($1, $2, $3)")
+        .bind(&query.filename)
+        .bind(format.as_str())
+        .bind(&body)
+        .execute(&state.pool)
+        .await;
+    if let Err(error) = saved {
+        return HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        }));
+    }
+
+    // The file only replaces the songs when it is the selected source
+    let mut diff = None;
+    if matches!(CatalogSource::current(state.clone()).await, Ok(CatalogSource::LocalFile)) {
+        match store_catalog(&rows, CatalogSource::LocalFile, state).await {
+            Ok(catalog_diff) => diff = Some(catalog_diff),
+            Err(error) => return HttpResponse::InternalServerError().json(json!({
+                "status": "ko",
+                "content": error.to_string(),
+            })),
+        }
+    }
+
+    HttpResponse::Ok().json(json!({
+        "status": "ok",
+        "content": rows.without_duplicates().songs.len(),
+        "diff": diff,
+    }))
+}
+
+
+impl CatalogSource {
+    pub fn from_config(value: &str) -> CatalogSource {
+        match value {
+            "local_file" => CatalogSource::LocalFile,
+            _ => CatalogSource::GoogleSheet,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            CatalogSource::GoogleSheet => "google_sheet",
+            CatalogSource::LocalFile => "local_file",
+        }
+    }
+
+    /// Source currently configured, Google Sheets when not set
+    pub async fn current(state: web::Data<AppState>) -> Result<CatalogSource, sqlx::Error> {
+        let value = get_config_value("catalog_source", state).await?;
+        Ok(CatalogSource::from_config(value.as_deref().unwrap_or_default()))
+    }
+
+    pub async fn fetch_songs(&self, state: web::Data<AppState>) -> Result<ImportedRows, CatalogError> {
+        match self {
+            CatalogSource::GoogleSheet => {
+                let sheet_ids = sheet_layout::sheet_ids(&get_config_value("google_sheet_id", state.clone()).await?.unwrap_or_default());
+                // Nothing to read must not empty the catalog
+                if sheet_ids.is_empty() {
+                    return Err(CatalogError::NoSheet);
+                }
+
+                let mut parts = vec![];
+                for sheet_id in sheet_ids {
+                    let layout = SheetLayout::for_sheet(&sheet_id, state.clone()).await?;
+                    for (source, range) in layout.sources(&sheet_id) {
+                        parts.push((sheet_id.clone(), range, source, layout.clone()));
+                    }
+                }
+
+                let ranges: Vec<(String, String)> = parts.iter().map(|(sheet_id, range, _, _)| (sheet_id.clone(), range.clone())).collect();
+                let mut contents = state.google.fetch_sheets(&ranges).await?;
+                debug!("{:?}", contents);
+                // Nothing could be read, the catalog is left as it is
+                if contents.iter().all(|content| content.is_err()) {
+                    if let Some(Err(error)) = contents.drain(..).next() {
+                        return Err(error.into());
+                    }
+                }
+
+                let mut imported = vec![];
+                for (content, (sheet_id, range, source, layout)) in contents.into_iter().zip(parts.iter()) {
+                    match content {
+                        Ok(content) => imported.push(content.transform_google_format_to_song(layout).with_source(source)),
+                        Err(error) => {
+                            warn!("Could not read {} of the sheet {}: {}", range, sheet_id, error);
+                            imported.push(ImportedRows::failed(source, error.to_string()));
+                        }
+                    }
+                }
+                Ok(ImportedRows::merge(imported))
+            }
+            CatalogSource::LocalFile => {
+                let file: Option<(Option<String>, String, String)> = sqlx::query_as("SELECT filename, format, content FROM catalog_files ORDER BY uploaded_at DESC, id DESC LIMIT 1")
+                    .fetch_optional(&state.pool)
+                    .await?;
+                let (filename, format, content) = file.ok_or(CatalogError::NoFile)?;
+                Ok(parse_catalog_file(&content, FileFormat::from_str(&format))?.with_source(&file_source(filename.as_deref())))
+            }
+        }
+    }
+
+    /// Fetch the songs from the source and make them the current catalog
+    pub async fn import(&self, state: web::Data<AppState>) -> Result<CatalogDiff, CatalogError> {
+        let rows = self.fetch_songs(state.clone()).await?;
+        Ok(store_catalog(&rows, *self, state).await?)
+    }
+}
+
+
+/// Songs of the catalog : from memory, then from the database, and from the source only when nothing was imported yet
+pub async fn catalog_songs(state: web::Data<AppState>) -> Result<Vec<Song>, CatalogError> {
+    if !state.is_playlist_cache_empty() {
+        if let Some(songs) = state.read_from_cache() {
+            return Ok(songs);
+        }
+    }
+
+    let songs = load_catalog(&state.pool).await?;
+    if !songs.is_empty() {
+        debug!("Song list loaded from the database.");
+        state.update_playlist_cache(songs.clone());
+        return Ok(songs);
+    }
+
+    debug!("Song list not imported yet, fetching it.");
+    CatalogSource::current(state.clone()).await?.import(state.clone()).await?;
+    Ok(state.read_from_cache().unwrap_or_default())
+}
+
+/// Replace the catalog saved in the database and the in-memory cache, returns what changed.
+/// Known songs keep their id, the ones missing from the import are deactivated unless the maestro added them.
+pub async fn store_catalog(rows: &ImportedRows, source: CatalogSource, state: web::Data<AppState>) -> Result<CatalogDiff, sqlx::Error> {
+    let mut tx = state.pool.begin().await?;
+    let (catalog, diff) = replace_catalog(rows, source, &mut tx).await?;
+    tx.commit().await?;
+
+    state.update_playlist_cache(catalog);
+    Ok(diff)
+}
+
+// Database part of store_catalog, run inside its transaction
+async fn replace_catalog(rows: &ImportedRows, source: CatalogSource, tx: &mut PgConnection) -> Result<(Vec<Song>, CatalogDiff), sqlx::Error> {
+    // A song listed twice would be updated twice by the same query
+    let rows = rows.without_duplicates();
+    let songs = rows.song_list();
+
+    // Only one import at a time, so the diff compares with the catalog really replaced
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('songs'))")
+        .execute(&mut *tx)
+        .await?;
+    let before: Vec<Song> = sqlx::query_as(CATALOG_QUERY)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let import_id: i32 = sqlx::query_scalar("INSERT INTO catalog_imports (source, song_count) VALUES ($1, $2) RETURNING id")
+        .bind(source.as_str())
+        .bind(songs.len() as i32)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    // The songs of a sheet that could not be read stay as they were
+    let failed_sources: Vec<String> = rows.failed.iter().map(|failed| failed.source.clone()).collect();
+    sqlx::query("UPDATE songs SET active = FALSE WHERE active AND NOT (source = ANY($1))")
+        .bind(&failed_sources)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT INTO songs (song_key, artist, title, lyrics_url, source, genre, language, year, duet, difficulty, duration, import_id, active)
+                 SELECT song_key, artist, title, lyrics_url, source, genre, language, year, duet, difficulty, duration, $12, TRUE
+                 FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::text[], $6::text[], $7::text[], $8::int4[], $9::bool[], $10::text[], $11::int4[])
+                     AS s(song_key, artist, title, lyrics_url, source, genre, language, year, duet, difficulty, duration)
+                 ON CONFLICT (song_key) DO UPDATE
+                 SET artist = EXCLUDED.artist, title = EXCLUDED.title, lyrics_url = EXCLUDED.lyrics_url, source = EXCLUDED.source,
+                     genre = EXCLUDED.genre, language = EXCLUDED.language, year = EXCLUDED.year, duet = EXCLUDED.duet,
+                     difficulty = EXCLUDED.difficulty, duration = EXCLUDED.duration, import_id = EXCLUDED.import_id, active = TRUE")
+        .bind(songs.iter().map(|song| song_key(&song.artist, &song.title)).collect::<Vec<String>>())
+        .bind(songs.iter().map(|song| song.artist.clone()).collect::<Vec<String>>())
+        .bind(songs.iter().map(|song| song.title.clone()).collect::<Vec<String>>())
+        .bind(songs.iter().map(|song| song.lyrics_url.clone()).collect::<Vec<String>>())
+        .bind(songs.iter().map(|song| song.source.clone().unwrap_or_default()).collect::<Vec<String>>())
+        .bind(songs.iter().map(|song| song.metadata.genre.clone()).collect::<Vec<Option<String>>>())
+        .bind(songs.iter().map(|song| song.metadata.language.clone()).collect::<Vec<Option<String>>>())
+        .bind(songs.iter().map(|song| song.metadata.year).collect::<Vec<Option<i32>>>())
+        .bind(songs.iter().map(|song| song.metadata.duet).collect::<Vec<bool>>())
+        .bind(songs.iter().map(|song| song.metadata.difficulty.clone()).collect::<Vec<Option<String>>>())
+        .bind(songs.iter().map(|song| song.metadata.duration).collect::<Vec<Option<i32>>>())
+        .bind(import_id)
+        .execute(&mut *tx)
+        .await?;
+    restore_local_songs(&mut *tx, Some(import_id)).await?;
+
+    let catalog: Vec<Song> = sqlx::query_as(CATALOG_QUERY)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let diff = CatalogDiff { failed: rows.failed, ..CatalogDiff::between(&before, &catalog, rows.skipped) };
+    Ok((catalog, diff))
+}
+
+// Source of the songs of an uploaded file
+fn file_source(filename: Option<&str>) -> String {
+    filename.filter(|name| !name.trim().is_empty()).unwrap_or("fichier importé").to_string()
+}
+
+/// Identity of a song in the catalog : artist and title without case, accents nor punctuation
+pub fn song_key(artist: &str, title: &str) -> String {
+    format!("{}|{}", normalize(artist), normalize(title))
+}
+
+pub fn normalize(text: &str) -> String {
+    deunicode(text)
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Give a key to the songs saved before the keys existed, so the next import matches them instead of
+/// adding them again with a new id. Songs sharing a key are merged in the first one, queue entries and
+/// lyrics included. Run at startup, before any import.
+pub async fn backfill_song_keys(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    merge_song_keys(&mut tx).await?;
+    tx.commit().await
+}
+
+// Database part of backfill_song_keys, run inside its transaction
+async fn merge_song_keys(tx: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('songs'))")
+        .execute(&mut *tx)
+        .await?;
+
+    let missing: Vec<(i32, String, String, bool)> = sqlx::query_as(
+            "SELECT id, artist, title, active FROM songs WHERE song_key IS NULL ORDER BY active DESC, id")
+        .fetch_all(&mut *tx)
+        .await?;
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let mut kept: HashMap<String, i32> = sqlx::query_as("SELECT song_key, id FROM songs WHERE song_key IS NOT NULL")
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .collect();
+
+    for (id, artist, title, active) in missing {
+        let key = song_key(&artist, &title);
+        let Some(&kept_id) = kept.get(&key) else {
+            sqlx::query("UPDATE songs SET song_key = $1 WHERE id = $2")
+                .bind(&key)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            kept.insert(key, id);
+            continue;
+        };
+
+        sqlx::query("UPDATE current_playlist SET song_id = $1 WHERE song_id = $2")
+            .bind(kept_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        // The lyrics of the kept song win
+        sqlx::query("UPDATE song_lyrics SET song_id = $1 WHERE song_id = $2 AND NOT EXISTS (SELECT 1 FROM song_lyrics WHERE song_id = $1)")
+            .bind(kept_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE songs SET active = active OR $1 WHERE id = $2")
+            .bind(active)
+            .bind(kept_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM songs WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    Ok(())
+}
+
+const CATALOG_QUERY: &str = "SELECT id, artist, title, lyrics_url, NULL::text AS singer, NULLIF(source, '') AS source,
+                             genre, language, year, duet, difficulty, duration
+                             FROM songs WHERE active ORDER BY id";
+
+/// Catalog saved by the last import, empty when nothing was imported yet
+pub async fn load_catalog<'e>(executor: impl PgExecutor<'e>) -> Result<Vec<Song>, sqlx::Error> {
+    sqlx::query_as(CATALOG_QUERY)
+        .fetch_all(executor)
+        .await
+}
+
+
+impl FileFormat {
+    fn detect(content_type: &str, filename: &str) -> FileFormat {
+        if content_type.contains("json") || filename.to_lowercase().ends_with(".json") {
+            FileFormat::Json
+        } else {
+            FileFormat::Csv
+        }
+    }
+
+    fn from_str(value: &str) -> FileFormat {
+        match value {
+            "json" => FileFormat::Json,
+            _ => FileFormat::Csv,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            FileFormat::Csv => "csv",
+            FileFormat::Json => "json",
+        }
+    }
+}
+
+
+pub fn parse_catalog_file(content: &str, format: FileFormat) -> Result<ImportedRows, CatalogError> {
+    // Spreadsheet exports often start with a byte order mark
+    let content = content.trim_start_matches('\u{feff}');
+
+    let rows = match format {
+        FileFormat::Json => {
+            let entries: Vec<serde_json::Value> = serde_json::from_str(content)
+                .map_err(|error| CatalogError::InvalidFile(error.to_string()))?;
+            let mut rows = ImportedRows::default();
+            // An entry is a row, numbered from 1 like the lines of a sheet
+            for (index, entry) in entries.into_iter().enumerate() {
+                match serde_json::from_value::<CatalogEntry>(entry) {
+                    Ok(entry) if !entry.title.trim().is_empty() && !entry.artist.trim().is_empty() => {
+                        let metadata = entry.metadata();
+                        let song = Song {
+                            id: (rows.songs.len() + 1) as i32,
+                            artist: entry.artist,
+                            title: entry.title,
+                            lyrics_url: entry.lyrics_url.unwrap_or_default(),
+                            singer: None,
+                            status: None,
+                            song_id: None,
+                            source: None,
+                            metadata,
+                        };
+                        rows.songs.push((index + 1, song));
+                    }
+                    _ => rows.skipped.push(SkippedRow { row: index + 1, reason: SkipReason::MissingColumns, source: None }),
+                }
+            }
+            rows
+        }
+        FileFormat::Csv => {
+            let rows = read_csv_rows(content)?;
+            let header = rows.first().map(|row| row.as_slice()).unwrap_or_default();
+            local_file_layout(header).read_rows(&rows, 1)
+        }
+    };
+
+    if rows.songs.is_empty() {
+        return Err(CatalogError::InvalidFile("no song found".to_string()));
+    }
+    Ok(rows)
+}
+
+fn read_csv_rows(content: &str) -> Result<Vec<Vec<String>>, CatalogError> {
+    // French spreadsheets export with semicolons
+    let first_line = content.lines().next().unwrap_or_default();
+    let delimiter = if first_line.matches(';').count() > first_line.matches(',').count() { b';' } else { b',' };
+
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(content.as_bytes())
+        .records()
+        .map(|record| {
+            record
+                .map(|record| record.iter().map(|cell| cell.trim().to_string()).collect())
+                .map_err(|error| CatalogError::InvalidFile(error.to_string()))
+        })
+        .collect()
+}
+
+// Columns are found by name in the header, a file without known names is read as title, artist, lyrics
+fn local_file_layout(header: &[String]) -> SheetLayout {
+    let find = |names: &[&str]| {
+        names
+            .iter()
+            .find(|name| header.iter().any(|column| column.trim().to_lowercase() == **name))
+            .map(|name| name.to_string())
+    };
+
+    match (find(&TITLE_COLUMNS), find(&ARTIST_COLUMNS)) {
+        (Some(title), Some(artist)) => SheetLayout {
+            range: String::new(),
+            has_header: true,
+            title_column: title,
+            artist_column: artist,
+            lyrics_url_column: find(&LYRICS_COLUMNS).unwrap_or_default(),
+            genre_column: find(&GENRE_COLUMNS).unwrap_or_default(),
+            language_column: find(&LANGUAGE_COLUMNS).unwrap_or_default(),
+            year_column: find(&YEAR_COLUMNS).unwrap_or_default(),
+            duet_column: find(&DUET_COLUMNS).unwrap_or_default(),
+            difficulty_column: find(&DIFFICULTY_COLUMNS).unwrap_or_default(),
+            duration_column: find(&DURATION_COLUMNS).unwrap_or_default(),
+            ..SheetLayout::default()
+        },
+        _ => SheetLayout {
+            has_header: false,
+            ..SheetLayout::default()
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::fs;
+
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    #[allow(non_snake_case)]
+    struct Secrets {
+        pub DATABASE_URL: String,
+    }
+
+    fn load_secrets() -> Secrets {
+        let path = Path::new("Secrets.toml");
+        let content = fs::read_to_string(path)
+            .expect("Failed to read Secrets.toml");
+        toml::from_str(&content)
+            .expect("Failed to parse Secrets.toml")
+    }
+
+    #[test]
+    fn test_parse_csv_with_header() {
+        let content = "\u{feff}Artiste;Titre;Année\nartiste 1;Chanson A;1984\n\nartiste 2;\"Chanson; B\";1999\n";
+        let rows = parse_catalog_file(content, FileFormat::Csv).unwrap();
+
+        assert_eq!(rows.song_list(), vec![
+            Song { metadata: SongMetadata { year: Some(1984), ..SongMetadata::default() }, ..Song::for_test(1, "artiste 1", "Chanson A") },
+            Song { metadata: SongMetadata { year: Some(1999), ..SongMetadata::default() }, ..Song::for_test(2, "artiste 2", "Chanson; B") },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_csv_without_header() {
+        let content = "Chanson A,artiste 1,http://paroles/a\nChanson B,artiste 2\n";
+        let songs = parse_catalog_file(content, FileFormat::Csv).unwrap().song_list();
+
+        assert_eq!(songs.len(), 2);
+        assert_eq!(songs[0].lyrics_url, "http://paroles/a");
+        assert_eq!(songs[1].title, "Chanson B");
+        assert_eq!(songs[1].artist, "artiste 2");
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let content = r#"[{"title": "Chanson A", "artist": "artiste 1", "lyrics_url": "http://paroles/a"},
+                          {"titre": "Chanson X"},
+                          {"titre": "Chanson B", "artiste": "artiste 2", "langue": "Anglais", "année": 1999, "duo": true, "durée": "3:05"}]"#;
+        let rows = parse_catalog_file(content, FileFormat::Json).unwrap();
+
+        assert_eq!(rows.skipped, vec![SkippedRow { row: 2, reason: SkipReason::MissingColumns, source: None }]);
+        assert_eq!(rows.song_list(), vec![
+            Song { lyrics_url: "http://paroles/a".to_string(), ..Song::for_test(1, "artiste 1", "Chanson A") },
+            Song {
+                metadata: SongMetadata { language: Some("Anglais".to_string()), year: Some(1999), duet: true, duration: Some(185), ..SongMetadata::default() },
+                ..Song::for_test(2, "artiste 2", "Chanson B")
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_invalid_file() {
+        assert!(matches!(parse_catalog_file("{\"title\": 1}", FileFormat::Json), Err(CatalogError::InvalidFile(_))));
+        assert!(matches!(parse_catalog_file("", FileFormat::Csv), Err(CatalogError::InvalidFile(_))));
+    }
+
+    #[actix_web::test]
+    async fn test_backfill_song_keys() {
+        let pool = PgPool::connect(&load_secrets().DATABASE_URL).await.expect("Fail to connect to Database");
+        // Rolled back at the end, the database is left as it was
+        let mut tx = pool.begin().await.unwrap();
+
+        let insert = "INSERT INTO songs (artist, title, active) VALUES ($1, $2, FALSE) RETURNING id";
+        let first: i32 = sqlx::query_scalar(insert).bind("Zaz").bind("Je veux (rattrapage)").fetch_one(&mut *tx).await.unwrap();
+        let second: i32 = sqlx::query_scalar(insert).bind("ZAZ ").bind("Je Veux - Rattrapage").fetch_one(&mut *tx).await.unwrap();
+        sqlx::query("INSERT INTO song_lyrics (song_id, format, content) VALUES ($1, 'text', 'Donnez-moi une suite au Ritz')")
+            .bind(second).execute(&mut *tx).await.unwrap();
+
+        merge_song_keys(&mut tx).await.unwrap();
+
+        let key: Option<String> = sqlx::query_scalar("SELECT song_key FROM songs WHERE id = $1").bind(first).fetch_one(&mut *tx).await.unwrap();
+        assert_eq!(key.as_deref(), Some("zaz|je veux rattrapage"));
+        let merged: Option<i32> = sqlx::query_scalar("SELECT id FROM songs WHERE id = $1").bind(second).fetch_optional(&mut *tx).await.unwrap();
+        assert_eq!(merged, None);
+        let lyrics_song: i32 = sqlx::query_scalar("SELECT song_id FROM song_lyrics WHERE content = 'Donnez-moi une suite au Ritz'")
+            .fetch_one(&mut *tx).await.unwrap();
+        assert_eq!(lyrics_song, first);
+
+        tx.rollback().await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_store_and_load_catalog() {
+        let pool = PgPool::connect(&load_secrets().DATABASE_URL).await.expect("Fail to connect to Database");
+        // Rolled back at the end, the catalog of the database is left as it was
+        let mut tx = pool.begin().await.unwrap();
+        let rows = parse_catalog_file("titre,artiste,paroles,langue,durée\nLa Bohème,Aznavour,http://paroles/a,Français,4:05\nAlors on danse,Stromae,,,\n", FileFormat::Csv).unwrap();
+
+        let (catalog, _) = replace_catalog(&rows, CatalogSource::LocalFile, &mut tx).await.unwrap();
+        assert_eq!(catalog.iter().map(|song| song.title.as_str()).collect::<Vec<&str>>(), vec!["La Bohème", "Alors on danse"]);
+        assert_eq!(catalog[0].metadata.language.as_deref(), Some("Français"));
+        assert_eq!(catalog[0].metadata.duration, Some(245));
+
+        // A restarted server only has the database
+        assert_eq!(load_catalog(&mut *tx).await.unwrap(), catalog);
+
+        // A new row in the sheet does not change the ids, a duplicate row is reported
+        let rows = parse_catalog_file("titre,artiste\nTous les mêmes,Stromae\nla boheme,AZNAVOUR\nAlors on danse,Stromae\nAlors on danse !,Stromae\n,Piaf\n", FileFormat::Csv).unwrap();
+        let (new_catalog, diff) = replace_catalog(&rows, CatalogSource::LocalFile, &mut tx).await.unwrap();
+        assert_eq!(new_catalog.len(), 3);
+        let id_of = |catalog: &[Song], title: &str| catalog.iter().find(|song| song_key(&song.artist, &song.title) == song_key("stromae", title)).map(|song| song.id);
+        assert_eq!(id_of(&new_catalog, "Alors on danse"), id_of(&catalog, "Alors on danse"));
+        assert_eq!(new_catalog.iter().find(|song| song.artist == "AZNAVOUR").map(|song| song.id), catalog.first().map(|song| song.id));
+
+        assert_eq!(diff.added.iter().map(|song| song.title.as_str()).collect::<Vec<&str>>(), vec!["Tous les mêmes"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].after.title, "la boheme");
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.skipped, vec![
+            SkippedRow { row: 5, reason: SkipReason::Duplicate, source: None },
+            SkippedRow { row: 6, reason: SkipReason::MissingColumns, source: None },
+        ]);
+
+        tx.rollback().await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_keep_songs_of_failed_sheet() {
+        let pool = PgPool::connect(&load_secrets().DATABASE_URL).await.expect("Fail to connect to Database");
+        let mut tx = pool.begin().await.unwrap();
+        let pop = parse_catalog_file("titre,artiste\nPapaoutai,Stromae\n", FileFormat::Csv).unwrap().with_source("Pop");
+        let rock = parse_catalog_file("titre,artiste\nBohemian Rhapsody,Queen\n", FileFormat::Csv).unwrap().with_source("Rock");
+        replace_catalog(&ImportedRows::merge(vec![pop.clone(), rock]), CatalogSource::GoogleSheet, &mut tx).await.unwrap();
+
+        // The Rock tab could not be read this time, its songs are not removed
+        let rows = ImportedRows::merge(vec![pop, ImportedRows::failed("Rock", "Google Sheets answered 403".to_string())]);
+        let (catalog, diff) = replace_catalog(&rows, CatalogSource::GoogleSheet, &mut tx).await.unwrap();
+        assert_eq!(catalog.iter().map(|song| song.title.as_str()).collect::<Vec<&str>>(), vec!["Papaoutai", "Bohemian Rhapsody"]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.failed, rows.failed);
+
+        tx.rollback().await.unwrap();
+    }
+
+    #[test]
+    fn test_song_key() {
+        assert_eq!(song_key("Édith  Piaf", "La Vie en rose !"), "edith piaf|la vie en rose");
+        assert_eq!(song_key("edith piaf", "la vie en Rosé"), song_key("Edith Piaf", "La vie en rose"));
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(FileFormat::detect("application/json", ""), FileFormat::Json);
+        assert_eq!(FileFormat::detect("application/octet-stream", "Chansons.JSON"), FileFormat::Json);
+        assert_eq!(FileFormat::detect("text/csv", "chansons.csv"), FileFormat::Csv);
+    }
+}
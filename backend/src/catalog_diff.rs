@@ -0,0 +1,230 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::catalog::song_key;
+use crate::song::Song;
+
+
+/// Songs read from a catalog source, with the row each one comes from
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ImportedRows {
+    pub songs: Vec<(usize, Song)>, // (row number in the source, song)
+    pub skipped: Vec<SkippedRow>,
+    pub failed: Vec<FailedSource>, // Sources that could not be read, their songs are kept as they were
+}
+
+/// Sheet or tab that could not be read during an import
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct FailedSource {
+    pub source: String,
+    pub error: String,
+}
+
+/// Row of the source that did not become a song
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SkippedRow {
+    pub row: usize,
+    pub reason: SkipReason,
+    #[serde(default)]
+    pub source: Option<String>, // Sheet or tab of the row when several are merged
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// No title or no artist
+    MissingColumns,
+    /// Same artist and title as an earlier row
+    Duplicate,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SongChange {
+    pub before: Song,
+    pub after: Song,
+}
+
+/// What an import changed in the catalog, shown to the maestro after a refresh
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub struct CatalogDiff {
+    pub added: Vec<Song>,
+    pub removed: Vec<Song>,
+    pub changed: Vec<SongChange>, // Same song with a different spelling, lyrics link or details
+    pub skipped: Vec<SkippedRow>,
+    pub failed: Vec<FailedSource>,
+}
+
+
+impl ImportedRows {
+    pub fn song_list(&self) -> Vec<Song> {
+        self.songs.iter().map(|(_, song)| song.clone()).collect()
+    }
+
+    /// Nothing read from a source, its songs are left as they were
+    pub fn failed(source: &str, error: String) -> ImportedRows {
+        ImportedRows { failed: vec![FailedSource { source: source.to_string(), error }], ..ImportedRows::default() }
+    }
+
+    /// Remember where the songs and the skipped rows come from
+    pub fn with_source(mut self, source: &str) -> ImportedRows {
+        for (_, song) in self.songs.iter_mut() {
+            song.source = Some(source.to_string());
+        }
+        for skipped_row in self.skipped.iter_mut() {
+            skipped_row.source = Some(source.to_string());
+        }
+        self
+    }
+
+    /// Put the rows of several sources one after the other, the first source wins on duplicates
+    pub fn merge(parts: Vec<ImportedRows>) -> ImportedRows {
+        parts.into_iter().fold(ImportedRows::default(), |mut merged, part| {
+            merged.songs.extend(part.songs);
+            merged.skipped.extend(part.skipped);
+            merged.failed.extend(part.failed);
+            merged
+        })
+    }
+
+    /// Keep the first row of every song, the other ones are reported as duplicates
+    pub fn without_duplicates(&self) -> ImportedRows {
+        let mut keys = HashSet::new();
+        let mut skipped = self.skipped.clone();
+
+        let songs = self
+            .songs
+            .iter()
+            .filter(|(row, song)| {
+                let is_new = keys.insert(song_key(&song.artist, &song.title));
+                if !is_new {
+                    skipped.push(SkippedRow { row: *row, reason: SkipReason::Duplicate, source: song.source.clone() });
+                }
+                is_new
+            })
+            .cloned()
+            .collect();
+
+        // Rows stay grouped by source, in the order the sources were read
+        let mut sources: Vec<Option<String>> = vec![];
+        for source in self.songs.iter().map(|(_, song)| &song.source).chain(self.skipped.iter().map(|skipped_row| &skipped_row.source)) {
+            if !sources.contains(source) {
+                sources.push(source.clone());
+            }
+        }
+        skipped.sort_by_key(|skipped_row| (sources.iter().position(|source| *source == skipped_row.source), skipped_row.row));
+        ImportedRows { songs, skipped, failed: self.failed.clone() }
+    }
+}
+
+
+impl CatalogDiff {
+    /// Compare the active catalog before and after an import, songs are matched on their key
+    pub fn between(before: &[Song], after: &[Song], skipped: Vec<SkippedRow>) -> CatalogDiff {
+        let by_key = |songs: &[Song]| -> HashMap<String, Song> {
+            songs.iter().map(|song| (song_key(&song.artist, &song.title), song.clone())).collect()
+        };
+        let before_by_key = by_key(before);
+        let after_by_key = by_key(after);
+
+        let mut diff = CatalogDiff { skipped, ..CatalogDiff::default() };
+        for song in after {
+            match before_by_key.get(&song_key(&song.artist, &song.title)) {
+                None => diff.added.push(song.clone()),
+                Some(old) if old.artist != song.artist || old.title != song.title || old.lyrics_url != song.lyrics_url || old.metadata != song.metadata => {
+                    diff.changed.push(SongChange { before: old.clone(), after: song.clone() })
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = before
+            .iter()
+            .filter(|song| !after_by_key.contains_key(&song_key(&song.artist, &song.title)))
+            .cloned()
+            .collect();
+
+        diff
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(id: i32, artist: &str, title: &str, lyrics_url: &str) -> Song {
+        Song { lyrics_url: lyrics_url.to_string(), ..Song::for_test(id, artist, title) }
+    }
+
+    #[test]
+    fn test_diff_between_catalogs() {
+        let before = vec![
+            song(1, "Aznavour", "La boheme", ""),
+            song(2, "Stromae", "Alors on danse", ""),
+            song(3, "Piaf", "La Vie en rose", "http://paroles/a"),
+        ];
+        let after = vec![
+            song(1, "Aznavour", "La Bohème", ""),
+            song(3, "Piaf", "La Vie en rose", "http://paroles/a"),
+            song(4, "Stromae", "Papaoutai", ""),
+        ];
+        let skipped = vec![SkippedRow { row: 7, reason: SkipReason::MissingColumns, source: None }];
+
+        let diff = CatalogDiff::between(&before, &after, skipped.clone());
+
+        assert_eq!(diff.added, vec![song(4, "Stromae", "Papaoutai", "")]);
+        assert_eq!(diff.removed, vec![song(2, "Stromae", "Alors on danse", "")]);
+        assert_eq!(diff.changed, vec![SongChange {
+            before: song(1, "Aznavour", "La boheme", ""),
+            after: song(1, "Aznavour", "La Bohème", ""),
+        }]);
+        assert_eq!(diff.skipped, skipped);
+    }
+
+    #[test]
+    fn test_without_duplicates() {
+        let rows = ImportedRows {
+            songs: vec![
+                (2, song(1, "Stromae", "Alors on danse", "")),
+                (4, song(2, "Piaf", "La Vie en rose", "")),
+                (5, song(3, "STROMAE", "Alors on danse !", "")),
+            ],
+            skipped: vec![SkippedRow { row: 3, reason: SkipReason::MissingColumns, source: None }],
+            failed: vec![],
+        };
+
+        let rows = rows.without_duplicates();
+
+        assert_eq!(rows.songs.iter().map(|(row, _)| *row).collect::<Vec<usize>>(), vec![2, 4]);
+        assert_eq!(rows.skipped, vec![
+            SkippedRow { row: 3, reason: SkipReason::MissingColumns, source: None },
+            SkippedRow { row: 5, reason: SkipReason::Duplicate, source: None },
+        ]);
+    }
+
+    #[test]
+    fn test_merge_sources() {
+        let carpe = ImportedRows {
+            songs: vec![(2, song(1, "Stromae", "Alors on danse", "")), (3, song(2, "Piaf", "La Vie en rose", ""))],
+            skipped: vec![SkippedRow { row: 4, reason: SkipReason::MissingColumns, source: None }],
+            failed: vec![],
+        };
+        let annecat = ImportedRows {
+            songs: vec![(2, song(1, "Aznavour", "La Bohème", "")), (3, song(2, "stromae", "Alors on danse", ""))],
+            skipped: vec![],
+            failed: vec![],
+        };
+        let rock = ImportedRows::failed("Rock", "Google Sheets answered 403".to_string());
+
+        let rows = ImportedRows::merge(vec![carpe.with_source("Carpe"), rock, annecat.with_source("Annecat")]).without_duplicates();
+
+        assert_eq!(
+            rows.songs.iter().map(|(_, song)| (song.title.as_str(), song.source.as_deref())).collect::<Vec<_>>(),
+            vec![("Alors on danse", Some("Carpe")), ("La Vie en rose", Some("Carpe")), ("La Bohème", Some("Annecat"))]
+        );
+        assert_eq!(rows.skipped, vec![
+            SkippedRow { row: 4, reason: SkipReason::MissingColumns, source: Some("Carpe".to_string()) },
+            SkippedRow { row: 3, reason: SkipReason::Duplicate, source: Some("Annecat".to_string()) },
+        ]);
+        assert_eq!(rows.failed, vec![FailedSource { source: "Rock".to_string(), error: "Google Sheets answered 403".to_string() }]);
+    }
+}
@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+
+use crate::catalog::normalize;
+use crate::song::Song;
+
+
+// Songs in a page when the request does not say, and the most a page can hold
+const DEFAULT_PER_PAGE: usize = 50;
+const MAX_PER_PAGE: usize = 200;
+
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortColumn {
+    #[default]
+    Artist,
+    Title,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query parameters of `/song-data`, everything is optional
+#[derive(Clone, PartialEq, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CatalogQuery {
+    pub page: Option<usize>, // Starts at 1
+    pub per_page: Option<usize>,
+    pub sort: SortColumn,
+    pub order: SortOrder,
+    pub letter: Option<String>, // First letter of the sorted column, "#" for anything else
+    pub source: Option<String>,
+    // Details of the songs, the case and accents are ignored
+    pub genre: Option<String>,
+    pub language: Option<String>,
+    pub decade: Option<i32>, // 1980 for the songs from 1980 to 1989
+    pub duet: Option<bool>,
+    pub difficulty: Option<String>,
+}
+
+/// One page of the catalog, with what the list needs to show its filters and page links
+#[derive(Clone, PartialEq, Serialize, Debug)]
+pub struct CatalogPage {
+    pub songs: Vec<Song>,
+    pub total: usize, // Songs matching the filters, on every page
+    pub page: usize,
+    pub per_page: usize,
+    pub sources: Vec<String>, // Every source of the catalog, whatever the filters
+    pub filters: CatalogFilters,
+}
+
+/// Values found in the details of the whole catalog, offered as filters
+#[derive(Clone, PartialEq, Serialize, Debug, Default)]
+pub struct CatalogFilters {
+    pub genres: Vec<String>,
+    pub languages: Vec<String>,
+    pub decades: Vec<i32>,
+    pub difficulties: Vec<String>,
+    pub duets: bool, // At least one song is a duet
+}
+
+
+impl CatalogQuery {
+    pub fn apply(&self, songs: &[Song]) -> CatalogPage {
+        let letter = self.letter.as_deref().and_then(|letter| letter.trim().chars().next()).map(|letter| letter.to_ascii_uppercase());
+        let sort_key = |song: &Song| match self.sort {
+            SortColumn::Artist => (normalize(&song.artist), normalize(&song.title)),
+            SortColumn::Title => (normalize(&song.title), normalize(&song.artist)),
+        };
+
+        let mut matching: Vec<((String, String), &Song)> = songs
+            .iter()
+            .filter(|song| self.source.is_none() || song.source == self.source)
+            .filter(|song| self.matches_metadata(song))
+            .map(|song| (sort_key(song), song))
+            .filter(|((first, _), _)| letter.is_none() || Some(first_letter(first)) == letter)
+            .collect();
+
+        matching.sort_by(|(key_a, a), (key_b, b)| key_a.cmp(key_b).then(a.id.cmp(&b.id)));
+        if self.order == SortOrder::Desc {
+            matching.reverse();
+        }
+
+        let per_page = self.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+        let page = self.page.unwrap_or(1).max(1);
+        CatalogPage {
+            total: matching.len(),
+            songs: matching
+                .into_iter()
+                .skip(page.saturating_sub(1).saturating_mul(per_page))
+                .take(per_page)
+                .map(|(_, song)| song.clone())
+                .collect(),
+            page,
+            per_page,
+            sources: sources(songs),
+            filters: CatalogFilters::of(songs),
+        }
+    }
+
+    fn matches_metadata(&self, song: &Song) -> bool {
+        let same_text = |filter: &Option<String>, value: &Option<String>| match filter {
+            Some(filter) => value.as_deref().is_some_and(|value| normalize(value) == normalize(filter)),
+            None => true,
+        };
+        same_text(&self.genre, &song.metadata.genre)
+            && same_text(&self.language, &song.metadata.language)
+            && same_text(&self.difficulty, &song.metadata.difficulty)
+            && (self.decade.is_none() || song.metadata.decade() == self.decade)
+            && (self.duet.is_none() || Some(song.metadata.duet) == self.duet)
+    }
+}
+
+impl CatalogFilters {
+    fn of(songs: &[Song]) -> CatalogFilters {
+        let mut decades: Vec<i32> = songs.iter().filter_map(|song| song.metadata.decade()).collect();
+        decades.sort();
+        decades.dedup();
+        CatalogFilters {
+            genres: distinct_texts(songs.iter().map(|song| &song.metadata.genre)),
+            languages: distinct_texts(songs.iter().map(|song| &song.metadata.language)),
+            decades,
+            difficulties: distinct_texts(songs.iter().map(|song| &song.metadata.difficulty)),
+            duets: songs.iter().any(|song| song.metadata.duet),
+        }
+    }
+}
+
+// Sorted values, "Rock" and "rock" are the same, the first spelling is kept
+fn distinct_texts<'a>(values: impl Iterator<Item = &'a Option<String>>) -> Vec<String> {
+    let mut texts: Vec<(String, String)> = vec![];
+    for value in values.flatten() {
+        let key = normalize(value);
+        if !texts.iter().any(|(known, _)| *known == key) {
+            texts.push((key, value.clone()));
+        }
+    }
+    texts.sort();
+    texts.into_iter().map(|(_, value)| value).collect()
+}
+
+// Letter a normalized text is listed under
+fn first_letter(text: &str) -> char {
+    match text.chars().next() {
+        Some(letter) if letter.is_ascii_alphabetic() => letter.to_ascii_uppercase(),
+        _ => '#',
+    }
+}
+
+// Sources in the order of the catalog
+fn sources(songs: &[Song]) -> Vec<String> {
+    let mut sources: Vec<String> = vec![];
+    for source in songs.iter().filter_map(|song| song.source.as_ref()) {
+        if !sources.contains(source) {
+            sources.push(source.clone());
+        }
+    }
+    sources
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::song_metadata::SongMetadata;
+
+    fn song(id: i32, artist: &str, title: &str, source: &str) -> Song {
+        Song { source: Some(source.to_string()), ..Song::for_test(id, artist, title) }
+    }
+
+    fn catalog() -> Vec<Song> {
+        vec![
+            song(1, "Stromae", "Papaoutai", "Carpe"),
+            song(2, "Édith Piaf", "La Vie en rose", "Carpe"),
+            song(3, "Aznavour", "La Bohème", "Annecat"),
+            song(4, "2Be3", "Partir un jour", "Annecat"),
+            song(5, "Stromae", "Alors on danse", "Carpe"),
+        ]
+    }
+
+    fn with_metadata(song: Song, genre: &str, language: &str, year: i32, duet: bool) -> Song {
+        Song {
+            metadata: SongMetadata {
+                genre: Some(genre.to_string()),
+                language: Some(language.to_string()),
+                year: Some(year),
+                duet,
+                ..SongMetadata::default()
+            },
+            ..song
+        }
+    }
+
+    fn ids(page: &CatalogPage) -> Vec<i32> {
+        page.songs.iter().map(|song| song.id).collect()
+    }
+
+    #[test]
+    fn test_sort_and_paginate() {
+        let query = CatalogQuery { per_page: Some(2), ..CatalogQuery::default() };
+        let page = query.apply(&catalog());
+        assert_eq!(ids(&page), vec![4, 3]);
+        assert_eq!(page.total, 5);
+        assert_eq!(page.sources, vec!["Carpe", "Annecat"]);
+
+        let query = CatalogQuery { page: Some(3), per_page: Some(2), ..CatalogQuery::default() };
+        assert_eq!(ids(&query.apply(&catalog())), vec![1]);
+
+        // Far past the end, without overflowing
+        let query = CatalogQuery { page: Some(usize::MAX), per_page: Some(2), ..CatalogQuery::default() };
+        let page = query.apply(&catalog());
+        assert_eq!(ids(&page), Vec::<i32>::new());
+        assert_eq!(page.page, usize::MAX);
+
+        let query = CatalogQuery { sort: SortColumn::Title, order: SortOrder::Desc, ..CatalogQuery::default() };
+        assert_eq!(ids(&query.apply(&catalog())), vec![4, 1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn test_filter_by_letter_and_source() {
+        // Accents are ignored, "Édith" is under E
+        let query = CatalogQuery { letter: Some("e".to_string()), ..CatalogQuery::default() };
+        assert_eq!(ids(&query.apply(&catalog())), vec![2]);
+
+        let query = CatalogQuery { letter: Some("#".to_string()), ..CatalogQuery::default() };
+        assert_eq!(ids(&query.apply(&catalog())), vec![4]);
+
+        let query = CatalogQuery { letter: Some("S".to_string()), source: Some("Carpe".to_string()), ..CatalogQuery::default() };
+        let page = query.apply(&catalog());
+        assert_eq!(ids(&page), vec![5, 1]);
+        assert_eq!(page.total, 2);
+    }
+
+    #[test]
+    fn test_filter_by_metadata() {
+        let mut songs = catalog();
+        songs[0] = with_metadata(songs[0].clone(), "Pop", "Français", 2013, false);
+        songs[1] = with_metadata(songs[1].clone(), "Chanson", "Français", 1946, false);
+        songs[3] = with_metadata(songs[3].clone(), "pop", "Français", 1997, true);
+
+        let query = CatalogQuery { genre: Some("POP".to_string()), ..CatalogQuery::default() };
+        assert_eq!(ids(&query.apply(&songs)), vec![4, 1]);
+
+        let query = CatalogQuery { language: Some("francais".to_string()), decade: Some(1940), ..CatalogQuery::default() };
+        assert_eq!(ids(&query.apply(&songs)), vec![2]);
+
+        let query = CatalogQuery { duet: Some(true), ..CatalogQuery::default() };
+        let page = query.apply(&songs);
+        assert_eq!(ids(&page), vec![4]);
+        assert_eq!(page.filters, CatalogFilters {
+            genres: vec!["Chanson".to_string(), "Pop".to_string()],
+            languages: vec!["Français".to_string()],
+            decades: vec![1940, 1990, 2010],
+            difficulties: vec![],
+            duets: true,
+        });
+    }
+}
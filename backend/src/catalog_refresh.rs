@@ -0,0 +1,191 @@
+use std::time::Duration;
+use actix_web::{web, get, Responder, HttpResponse};
+use actix_web::middleware::from_fn;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+use log::{info, warn};
+
+use crate::auth::require_admin;
+use crate::catalog::{CatalogError, CatalogSource};
+use crate::catalog_diff::CatalogDiff;
+use crate::config::get_config_value;
+use crate::events::LiveEvent;
+use crate::state::AppState;
+
+
+// Config entry holding the refresh interval, 0 disables the automatic refresh
+const INTERVAL_CONFIG_NAME: &str = "catalog_refresh_minutes";
+
+// First retry after a failed refresh, doubled after each failure up to the interval
+const MIN_RETRY: Duration = Duration::from_secs(60);
+
+
+/// Outcome of the catalog refreshes, manual or automatic
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub struct RefreshStatus {
+    pub interval_minutes: u64,
+    pub in_progress: bool,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_error: Option<DateTime<Utc>>,
+    pub last_error_message: Option<String>,
+    pub consecutive_failures: u32,
+    pub next_refresh: Option<DateTime<Utc>>,
+}
+
+
+#[get("/catalog-refresh-status", wrap = "from_fn(require_admin)")]
+async fn catalog_refresh_status(state: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(state.refresh_status())
+}
+
+
+impl RefreshStatus {
+    pub fn record(&mut self, result: &Result<(), &CatalogError>) {
+        self.in_progress = false;
+        match result {
+            Ok(()) => {
+                self.last_success = Some(Utc::now());
+                self.consecutive_failures = 0;
+            }
+            Err(error) => {
+                self.last_error = Some(Utc::now());
+                self.last_error_message = Some(error.to_string());
+                self.consecutive_failures += 1;
+            }
+        }
+    }
+}
+
+
+/// Start the background task refreshing the catalog from its source on the configured interval
+pub async fn spawn_catalog_refresh(state: web::Data<AppState>) {
+    // The last import before a restart still counts as the last success
+    let last_import: Result<Option<DateTime<Utc>>, sqlx::Error> = sqlx::query_scalar("SELECT MAX(imported_at) AT TIME ZONE current_setting('TimeZone') FROM catalog_imports")
+        .fetch_one(&state.pool)
+        .await;
+    if let Ok(last_import) = last_import {
+        state.update_refresh_status(|status| status.last_success = last_import);
+    }
+
+    actix_web::rt::spawn(refresh_loop(state));
+}
+
+async fn refresh_loop(state: web::Data<AppState>) {
+    let mut events = state.events.subscribe();
+    let mut interval = refresh_interval(state.clone()).await;
+    let mut last_attempt = Instant::now();
+
+    loop {
+        let failures = state.refresh_status().consecutive_failures;
+        let deadline = interval.map(|interval| last_attempt + next_delay(interval, failures));
+        state.update_refresh_status(|status| {
+            status.interval_minutes = interval.map(|interval| interval.as_secs() / 60).unwrap_or(0);
+            status.next_refresh = deadline.map(|deadline| Utc::now() + deadline.saturating_duration_since(Instant::now()));
+        });
+
+        tokio::select! {
+            _ = wait_until(deadline) => {
+                last_attempt = Instant::now();
+                // The songs keep being served from the cache until the new catalog is stored
+                match refresh_catalog(state.clone()).await {
+                    Ok(_) => info!("Catalog refreshed"),
+                    Err(error) => warn!("Catalog refresh failed: {}", error),
+                }
+            }
+            event = events.recv() => match event {
+                Ok(LiveEvent::Config { name, .. }) if name == INTERVAL_CONFIG_NAME => {
+                    interval = refresh_interval(state.clone()).await;
+                }
+                Err(RecvError::Lagged(_)) => {
+                    interval = refresh_interval(state.clone()).await;
+                }
+                Err(RecvError::Closed) => return,
+                Ok(_) => {}
+            }
+        }
+    }
+}
+
+/// Import the catalog from the configured source and record the outcome
+pub async fn refresh_catalog(state: web::Data<AppState>) -> Result<CatalogDiff, CatalogError> {
+    state.update_refresh_status(|status| status.in_progress = true);
+    let result = match CatalogSource::current(state.clone()).await {
+        Ok(source) => source.import(state.clone()).await,
+        Err(error) => Err(error.into()),
+    };
+    state.update_refresh_status(|status| status.record(&result.as_ref().map(|_| ())));
+    result
+}
+
+async fn refresh_interval(state: web::Data<AppState>) -> Option<Duration> {
+    let value = get_config_value(INTERVAL_CONFIG_NAME, state).await.ok().flatten();
+    interval_from_config(value.as_deref().unwrap_or_default())
+}
+
+fn interval_from_config(value: &str) -> Option<Duration> {
+    match value.trim().parse::<u64>() {
+        Ok(minutes) if minutes > 0 => Some(Duration::from_secs(minutes * 60)),
+        _ => None,
+    }
+}
+
+/// Wait a full interval after a success, retry sooner after a failure with an exponential backoff
+fn next_delay(interval: Duration, consecutive_failures: u32) -> Duration {
+    if consecutive_failures == 0 {
+        return interval;
+    }
+    let backoff = MIN_RETRY.saturating_mul(2u32.saturating_pow(consecutive_failures - 1));
+    backoff.min(interval)
+}
+
+async fn wait_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_from_config() {
+        assert_eq!(interval_from_config("30"), Some(Duration::from_secs(1800)));
+        assert_eq!(interval_from_config(" 5 "), Some(Duration::from_secs(300)));
+        assert_eq!(interval_from_config("0"), None);
+        assert_eq!(interval_from_config("-3"), None);
+        assert_eq!(interval_from_config(""), None);
+    }
+
+    #[test]
+    fn test_next_delay() {
+        let interval = Duration::from_secs(30 * 60);
+        assert_eq!(next_delay(interval, 0), interval);
+        assert_eq!(next_delay(interval, 1), Duration::from_secs(60));
+        assert_eq!(next_delay(interval, 2), Duration::from_secs(120));
+        assert_eq!(next_delay(interval, 4), Duration::from_secs(480));
+        assert_eq!(next_delay(interval, 6), interval);
+        assert_eq!(next_delay(interval, 100), interval);
+    }
+
+    #[test]
+    fn test_record_refresh() {
+        let mut status = RefreshStatus { in_progress: true, ..RefreshStatus::default() };
+
+        status.record(&Err(&CatalogError::NoFile));
+        status.record(&Err(&CatalogError::NoFile));
+        assert_eq!(status.consecutive_failures, 2);
+        assert!(status.last_error.is_some());
+        assert_eq!(status.last_error_message.as_deref(), Some("No catalog file has been uploaded"));
+        assert!(!status.in_progress);
+
+        status.record(&Ok(()));
+        assert_eq!(status.consecutive_failures, 0);
+        assert!(status.last_success.is_some());
+        assert!(status.last_error.is_some());
+    }
+}
@@ -1,12 +1,15 @@
 use serde::{Deserialize, Serialize};
 use actix_web::{web, post, Responder, HttpResponse};
+use actix_web::middleware::from_fn;
 use sqlx::FromRow;
 use crate::state::AppState; 
+use crate::auth::require_admin;
+use crate::events::LiveEvent;
 use serde_json::json;
 
 
 
-#[post("/change-config")]
+#[post("/change-config", wrap = "from_fn(require_admin)")]
 async fn change_config(content: web::Json<Config>, state: web::Data<AppState>) -> impl Responder {
 
     let res_content = content.into_inner().change_config_in_db(state).await   ;
@@ -48,12 +51,23 @@ pub struct Config {
 
 }
 
+/// Value of a config entry, None when it does not exist
+pub async fn get_config_value(name: &str, state: web::Data<AppState>) -> Result<Option<String>, sqlx::Error> {
+    let value: Option<Option<String>> = sqlx::query_scalar("select value FROM config WHERE name = $1")
+        .bind(name)
+        .fetch_optional(&state.pool)
+        .await?;
+    Ok(value.flatten())
+}
+
 impl Config {
     pub async fn change_config_in_db(&self, state: web::Data<AppState>) -> Result<Config, sqlx::Error>{
-        sqlx::query_as("update config set value = $1 where name = $2 RETURNING id, name, value")
+        let config: Config = sqlx::query_as("update config set value = $1 where name = $2 RETURNING id, name, value")
             .bind(&self.value)
             .bind(&self.name)
-            .fetch_one(&state.pool).await
+            .fetch_one(&state.pool).await?;
+        state.notify(LiveEvent::Config { name: config.name.clone(), value: config.value.clone() });
+        Ok(config)
     }
 
     pub async fn get_config_from_name(&self, state: web::Data<AppState>) -> Result<Config, sqlx::Error> {
@@ -72,11 +86,9 @@ mod tests {
     use actix_web::{test, App};
     use sqlx::PgPool;
     use crate::state::AppState;
-    use std::sync::{Arc, RwLock};
-    use crate::song::Song;
+    use crate::auth::{test_admin_header, TEST_SECRET};
     use std::path::Path;
     use std::fs;
-    use toml;
     use serde::Deserialize;
 
 
@@ -114,8 +126,7 @@ mod tests {
     #[actix_web::test]
     async fn test_change_config() {
         let pool = setup_test_db().await;
-        let playlist_cache = Arc::new(RwLock::new(Vec::<Song>::new()));
-        let state = web::Data::new(AppState { pool, playlist_cache });
+        let state = web::Data::new(AppState { jwt_secret: Some(TEST_SECRET.to_string()), ..AppState::new(pool) });
         let app = test::init_service(App::new().app_data(state.clone()).service(change_config)).await;
 
         sqlx::query("update config set value='yes' where name='open'")
@@ -125,6 +136,7 @@ mod tests {
         
         let req = test::TestRequest::post()
             .uri("/change-config")
+            .insert_header(test_admin_header())
             .set_json(&Config { id: 1, name: "open".to_string(), value: "no".to_string() })
             .to_request();
         
@@ -148,8 +160,7 @@ mod tests {
     #[actix_web::test]
     async fn test_get_config() {
         let pool = setup_test_db().await;
-        let playlist_cache = Arc::new(RwLock::new(Vec::<Song>::new()));
-        let state = web::Data::new(AppState { pool, playlist_cache });
+        let state = web::Data::new(AppState::new(pool));
         let app = test::init_service(App::new().app_data(state.clone()).service(get_config)).await;
 
         sqlx::query("update config set value='yes' where name='open'")
@@ -1,12 +1,15 @@
 use serde::{Deserialize, Serialize};
 use actix_web::{web, post, Responder, HttpResponse};
+use actix_web::middleware::from_fn;
 use sqlx::FromRow;
 use crate::state::AppState; 
+use crate::auth::require_admin;
 use serde_json::json;
+use std::collections::HashSet;
 
 
 
-#[post("/add-content")]
+#[post("/add-content", wrap = "from_fn(require_admin)")]
 async fn add_content(content: web::Json<Content>, state: web::Data<AppState>) -> impl Responder {
 
     let res_content = content.into_inner().insert_suggestion_into_db(state).await   ;
@@ -48,11 +51,12 @@ pub struct Content {
 }
 
 impl Content {
+    /// Saved without the tags and attributes that are not allowed
     pub async fn insert_suggestion_into_db(&self, state: web::Data<AppState>) -> Result<Content, sqlx::Error>{
         sqlx::query_as("INSERT INTO content(id, content_text) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE 
                             SET content_text = excluded.content_text RETURNING id, content_text")
             .bind(&self.id)
-            .bind(&self.content_text)
+            .bind(sanitize(&self.content_text))
             .fetch_one(&state.pool).await
     }
 
@@ -73,13 +77,41 @@ impl Content {
         }        
     }
 
+    /// Sanitized again, the blocks saved before the sanitization can still hold anything
     pub async fn get_content_from_id(&self, state: web::Data<AppState>) -> Result<Content, sqlx::Error> {
-        sqlx::query_as("select id, content_text FROM content WHERE id = $1")
+        let content: Content = sqlx::query_as("select id, content_text FROM content WHERE id = $1")
         .bind(&self.id)
         .fetch_one(&state.pool)
-        .await
+        .await?;
+        Ok(Content { content_text: sanitize(&content.content_text), ..content })
     }
 
 }
 
 
+/// HTML of a content block shown to every guest: formatting tags and links only,
+/// without scripts, styles or event handlers
+pub fn sanitize(html: &str) -> String {
+    ammonia::Builder::default()
+        .add_generic_attributes(HashSet::from(["class"]))
+        .add_tag_attributes("a", HashSet::from(["target"]))
+        .clean(html)
+        .to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("<p class=\"w3-center\">Bienvenue <b>ce soir</b> !</p>"), "<p class=\"w3-center\">Bienvenue <b>ce soir</b> !</p>");
+        assert_eq!(sanitize("Salut<script>alert('xss')</script>"), "Salut");
+        assert_eq!(sanitize("<img src=\"x\" onerror=\"alert(1)\">"), "<img src=\"x\">");
+        assert_eq!(sanitize("<a href=\"javascript:alert(1)\">lien</a>"), "<a rel=\"noopener noreferrer\">lien</a>");
+        assert_eq!(sanitize("<a href=\"https://carpe.fr\" target=\"_blank\">site</a>"),
+            "<a href=\"https://carpe.fr\" target=\"_blank\" rel=\"noopener noreferrer\">site</a>");
+        assert_eq!(sanitize("<div style=\"position: fixed\">x</div><iframe src=\"https://evil\"></iframe>"), "<div>x</div>");
+    }
+}
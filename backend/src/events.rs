@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use actix_web::{web, get, Responder, HttpResponse, Error};
+use actix_web::http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use actix_web::web::Bytes;
+use std::time::Duration;
+use tokio::time::interval;
+use tokio_stream::{once, StreamExt};
+use tokio_stream::wrappers::{BroadcastStream, IntervalStream};
+
+use crate::state::AppState;
+
+
+// Comment lines keep proxies from closing an idle connection
+const KEEP_ALIVE_SECONDS: u64 = 15;
+
+
+/// Change pushed to every connected client
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LiveEvent {
+    /// The queue changed and must be fetched again
+    Playlist,
+    /// A config value changed
+    Config { name: String, value: String },
+    /// A suggestion was sent, voted for or moderated
+    Suggestions,
+    /// Events may have been missed, everything must be fetched again
+    Resync,
+}
+
+impl LiveEvent {
+    fn to_sse(&self) -> String {
+        format!("data: {}\n\n", serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+
+// Server-Sent Events stream of the live changes
+#[get("/events")]
+async fn live_events(state: web::Data<AppState>) -> impl Responder {
+
+    // A lagging client lost some events, it gets a resync instead
+    let updates = BroadcastStream::new(state.events.subscribe())
+        .map(|event| event.unwrap_or(LiveEvent::Resync).to_sse());
+
+    let keep_alive = IntervalStream::new(interval(Duration::from_secs(KEEP_ALIVE_SECONDS)))
+        .map(|_| ": keep-alive\n\n".to_string());
+
+    // The first resync lets a reconnecting client catch up
+    let stream = once(LiveEvent::Resync.to_sse())
+        .chain(updates.merge(keep_alive))
+        .map(|message| Ok::<_, Error>(Bytes::from(message)));
+
+    HttpResponse::Ok()
+        .insert_header((CONTENT_TYPE, "text/event-stream"))
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        .streaming(stream)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_live_event_format() {
+        assert_eq!(LiveEvent::Playlist.to_sse(), "data: {\"kind\":\"playlist\"}\n\n");
+        assert_eq!(
+            LiveEvent::Config { name: "open".to_string(), value: "yes".to_string() }.to_sse(),
+            "data: {\"kind\":\"config\",\"name\":\"open\",\"value\":\"yes\"}\n\n"
+        );
+    }
+}
@@ -1,20 +1,31 @@
+use std::fmt;
 use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use serde_json::Value;
 use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
-use reqwest::Client;
-use std::time::SystemTime;
-use std::time::UNIX_EPOCH;
+use reqwest::{Client, StatusCode, Url};
 use serde::{Deserialize, Serialize};
-use crate::song::Song;
+use tokio::sync::Mutex;
+use crate::catalog_diff::ImportedRows;
+use crate::sheet_layout::SheetLayout;
 use log::debug;
 
 
+const DEFAULT_OAUTH_URL: &str = "https://oauth2.googleapis.com/token";
+const DEFAULT_SHEETS_URL: &str = "https://sheets.googleapis.com/v4/spreadsheets/";
+
+// A cached token is renewed this long before it expires
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[allow(non_snake_case)]
 pub struct GoogleSheetResponse {
     range: String,
     majorDimension: String,
-    values: Vec<Vec<String>>, // Nested vectors for rows and columns
+    #[serde(default)]
+    values: Vec<Vec<String>>, // Nested vectors for rows and columns, missing when the range is empty
 }
 
 // JWT claims for Google OAuth2
@@ -27,101 +38,217 @@ struct Claims {
     iat: usize,        // Issued at
 }
 
+// Answer of the OAuth2 token endpoint
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    expires_in: Option<u64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum GoogleError {
+    MissingKeyPath,
+    KeyFile(std::io::Error),
+    InvalidKey(String),
+    Signing(jsonwebtoken::errors::Error),
+    Request(reqwest::Error),
+    Auth(String),
+    Config(String),
+    Sheets { status: u16, message: String },
+}
+
+impl From<reqwest::Error> for GoogleError {
+    fn from(error: reqwest::Error) -> Self {
+        GoogleError::Request(error)
+    }
+}
 
-// Function to generate an access token
-pub async fn get_access_token(key: &Value) -> Result<String, reqwest::Error> {
-   // let key: serde_json::Value = serde_json::from_str(service_account_key).expect("google key not in right format");
-    let private_key = key["private_key"].as_str().expect("private_key missing");
-    let client_email = key["client_email"].as_str().expect("client_email missing");
-
-
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as usize;
-    let claims = Claims {
-        iss: client_email.to_string(),
-        scope: "https://www.googleapis.com/auth/spreadsheets.readonly".to_string(),
-        aud: "https://oauth2.googleapis.com/token".to_string(),
-        exp: now + 3600,
-        iat: now,
-    };
-
-    let jwt = encode(
-        &Header::new(Algorithm::RS256),
-        &claims,
-        &EncodingKey::from_rsa_pem(private_key.as_bytes()).unwrap(),
-    )
-    .unwrap();
-
-    let client = Client::new();
-    let response = client
-        .post("https://oauth2.googleapis.com/token")
-        .form(&[
-            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
-            ("assertion", &jwt),
-        ])
-        .send()
-        .await?;
-
-    let response_json: serde_json::Value = response.json().await?;
-    Ok(response_json["access_token"].as_str().unwrap().to_string())
+impl From<jsonwebtoken::errors::Error> for GoogleError {
+    fn from(error: jsonwebtoken::errors::Error) -> Self {
+        GoogleError::Signing(error)
+    }
 }
 
+impl fmt::Display for GoogleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoogleError::MissingKeyPath => write!(f, "GOOGLE_API_KEY_PATH not set"),
+            GoogleError::KeyFile(error) => write!(f, "Failed to read Google API key file: {}", error),
+            GoogleError::InvalidKey(error) => write!(f, "Invalid Google API key: {}", error),
+            GoogleError::Signing(error) => write!(f, "Failed to sign the Google token request: {}", error),
+            GoogleError::Request(error) => write!(f, "Error fetching document: {}", error),
+            GoogleError::Auth(error) => write!(f, "Failed to authenticate to Google: {}", error),
+            GoogleError::Config(error) => write!(f, "Invalid Google configuration: {}", error),
+            GoogleError::Sheets { status, message } => write!(f, "Google Sheets answered {}: {}", status, message),
+        }
+    }
+}
 
-// Fetch data from Google Sheets
-pub async fn fetch_google_sheet(sheet_id: String) -> Result<GoogleSheetResponse, reqwest::Error> {
+impl GoogleError {
+    /// The server is misconfigured, as opposed to Google being unreachable or refusing the request
+    pub fn is_configuration(&self) -> bool {
+        matches!(self, GoogleError::MissingKeyPath | GoogleError::KeyFile(_) | GoogleError::InvalidKey(_) | GoogleError::Signing(_) | GoogleError::Config(_))
+    }
+}
 
-    //let service_account_key = std::fs::read_to_string(ACCOUNT_KEY_FILE).expect("Service account file missing");
-    //let service_account_key = std::env::var("GOOGLE_API_KEY").expect("Secret was not found");
-    let key_path = std::env::var("GOOGLE_API_KEY_PATH").expect("GOOGLE_API_KEY_PATH not set");
-    let key_file = fs::read_to_string(key_path).expect("Failed to read Google API key file");
-    let service_account_key: Value = serde_json::from_str(&key_file).expect("Invalid JSON in Google API key");
 
-    
-    let access_token = get_access_token(&service_account_key).await.expect("Failed to authenticate");
-    
-    //let sheet_id: &'static str = "1KWhp9nuuA4WrbEk2IssQUBVCPjVT6WX9gjuV9qFo7AI"; 
-    
-    let range = "A:D"; // TODO : put in a config file
-   
+#[derive(Clone, Debug)]
+struct AccessToken {
+    value: String,
+    expires_at: Instant,
+}
+
+/// Google Sheets API client, the access token is shared by every request until it expires
+#[derive(Clone, Debug)]
+pub struct GoogleClient {
+    http: Client,
+    oauth_url: String,
+    sheets_url: String,
+    key_path: Option<String>,
+    token: Arc<Mutex<Option<AccessToken>>>,
+}
+
+impl GoogleClient {
+    /// Built from `GOOGLE_API_KEY_PATH`, the Google URLs can be overridden with `GOOGLE_OAUTH_URL` and `GOOGLE_SHEETS_URL`
+    pub fn from_env() -> GoogleClient {
+        GoogleClient::new(
+            std::env::var("GOOGLE_OAUTH_URL").unwrap_or_else(|_| DEFAULT_OAUTH_URL.to_string()),
+            std::env::var("GOOGLE_SHEETS_URL").unwrap_or_else(|_| DEFAULT_SHEETS_URL.to_string()),
+            std::env::var("GOOGLE_API_KEY_PATH").ok(),
+        )
+    }
+
+    pub fn new(oauth_url: String, sheets_url: String, key_path: Option<String>) -> GoogleClient {
+        GoogleClient {
+            http: Client::new(),
+            oauth_url,
+            sheets_url,
+            key_path,
+            token: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Cached token, a new one is requested when it is about to expire
+    async fn access_token(&self) -> Result<String, GoogleError> {
+        // Holding the lock while requesting makes concurrent fetches share the new token
+        let mut token = self.token.lock().await;
+        if let Some(current) = token.as_ref() {
+            if current.expires_at > Instant::now() + TOKEN_EXPIRY_MARGIN {
+                return Ok(current.value.clone());
+            }
+        }
+
+        let new_token = self.request_access_token().await?;
+        let value = new_token.value.clone();
+        *token = Some(new_token);
+        Ok(value)
+    }
+
+    async fn forget_access_token(&self) {
+        *self.token.lock().await = None;
+    }
+
+    // Sign a JWT with the service account key and exchange it for an access token
+    async fn request_access_token(&self) -> Result<AccessToken, GoogleError> {
+        let key_path = self.key_path.as_ref().ok_or(GoogleError::MissingKeyPath)?;
+        let key_file = fs::read_to_string(key_path).map_err(GoogleError::KeyFile)?;
+        let key: Value = serde_json::from_str(&key_file).map_err(|error| GoogleError::InvalidKey(error.to_string()))?;
+        let private_key = key["private_key"].as_str().ok_or_else(|| GoogleError::InvalidKey("private_key missing".to_string()))?;
+        let client_email = key["client_email"].as_str().ok_or_else(|| GoogleError::InvalidKey("client_email missing".to_string()))?;
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as usize;
+        let claims = Claims {
+            iss: client_email.to_string(),
+            scope: "https://www.googleapis.com/auth/spreadsheets.readonly".to_string(),
+            aud: self.oauth_url.clone(),
+            exp: now + 3600,
+            iat: now,
+        };
+
+        let jwt = encode(
+            &Header::new(Algorithm::RS256),
+            &claims,
+            &EncodingKey::from_rsa_pem(private_key.as_bytes())?,
+        )?;
+
+        let response = self.http
+            .post(&self.oauth_url)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", &jwt),
+            ])
+            .send()
+            .await?;
+
+        let status = response.status();
+        let token: TokenResponse = response.json().await?;
+        match token.access_token {
+            Some(value) if status.is_success() => Ok(AccessToken {
+                value,
+                expires_at: Instant::now() + Duration::from_secs(token.expires_in.unwrap_or(3600)),
+            }),
+            _ => Err(GoogleError::Auth(
+                token.error_description.or(token.error).unwrap_or_else(|| format!("no access token ({})", status)),
+            )),
+        }
+    }
 
+    // Fetch data from Google Sheets
+    pub async fn fetch_sheet(&self, sheet_id: &str, range: &str) -> Result<GoogleSheetResponse, GoogleError> {
+        // the range may contain a tab name with spaces, it has to be encoded as a path segment
+        let mut url = Url::parse(&self.sheets_url).map_err(|error| GoogleError::Config(format!("invalid Sheets URL: {}", error)))?;
+        url.path_segments_mut()
+            .map_err(|_| GoogleError::Config("invalid Sheets URL".to_string()))?
+            .pop_if_empty()
+            .extend([sheet_id, "values", range]);
+        url.set_query(Some("valueRenderOption=FORMATTED_VALUE"));
 
-    let url = format!(
-       "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}?valueRenderOption=FORMATTED_VALUE",
-        sheet_id, range
-    );
+        let access_token = self.access_token().await?;
+        let response = self.http
+            .get(url)
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+        debug!("{:?}", response);
 
-    let client = Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token)
-        .send()
-        .await?;
-    debug!("{:?}", response);
+        let status = response.status();
+        if !status.is_success() {
+            // A revoked token must not be reused for the next fetch
+            if status == StatusCode::UNAUTHORIZED {
+                self.forget_access_token().await;
+            }
+            let body: Value = response.json().await.unwrap_or_default();
+            let message = body["error"]["message"].as_str().unwrap_or_else(|| status.canonical_reason().unwrap_or_default()).to_string();
+            return Err(GoogleError::Sheets { status: status.as_u16(), message });
+        }
 
-    response.json::<GoogleSheetResponse>().await
+        Ok(response.json::<GoogleSheetResponse>().await?)
+    }
+
+    /// Fetch several (sheet id, range) at once, the first failure fails them all
+    /// Fetch several ranges at once, one broken sheet does not stop the other ones
+    pub async fn fetch_sheets(&self, ranges: &[(String, String)]) -> Result<Vec<Result<GoogleSheetResponse, GoogleError>>, GoogleError> {
+        // The token is requested once before the concurrent fetches share it
+        self.access_token().await?;
+        Ok(futures::future::join_all(ranges.iter().map(|(sheet_id, range)| self.fetch_sheet(sheet_id, range))).await)
+    }
 }
 
 impl GoogleSheetResponse {
-    pub fn transform_google_format_to_song(&self) -> Vec<Song>{
-        self
-            .values.clone()
-            .into_iter()
-            .skip(1)//skipping the fist element (column names)
-            .enumerate()
-            .filter_map(|(i, row)| {
-                // Attempt to map each row to a Song
-                if let (Some(artist), Some(title), Some(lyrics)) = (row.get(1), row.get(0), row.get(2)) {
-                    Some(Song {
-                        id: (i + 1) as i32,
-                        artist: artist.clone(),
-                        title: title.clone(),
-                        lyrics_url: lyrics.clone(),
-                        singer:None
-                    })
-                } else {
-                    None // Skip rows with invalid data
-                }
-            })
-            .collect()
+    pub fn transform_google_format_to_song(&self, layout: &SheetLayout) -> ImportedRows {
+        layout.read_rows(&self.values, self.first_row())
+    }
+
+    // Number of the first returned row, read from the A1 notation of the range ("'Feuille 1'!A5:D120" -> 5)
+    fn first_row(&self) -> usize {
+        let cells = self.range.rsplit('!').next().unwrap_or_default();
+        let first_cell = cells.split(':').next().unwrap_or_default();
+        first_cell
+            .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+            .parse()
+            .unwrap_or(1)
     }
 }
 
@@ -129,6 +256,119 @@ impl GoogleSheetResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::song::Song;
+    use crate::catalog::CatalogError;
+    use crate::catalog_diff::{SkipReason, SkippedRow};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use actix_web::{web, App, HttpServer, HttpResponse};
+    use actix_web::http::StatusCode;
+    use serde_json::json;
+
+    // Local stand-in for the OAuth and Sheets endpoints, answers the sheet "broken" with an error
+    async fn start_fake_google(token_requests: Arc<AtomicUsize>) -> String {
+        let server = HttpServer::new(move || {
+            let token_requests = token_requests.clone();
+            App::new()
+                .route("/token", web::post().to(move || {
+                    let token_requests = token_requests.clone();
+                    async move {
+                        token_requests.fetch_add(1, Ordering::SeqCst);
+                        HttpResponse::Ok().json(json!({"access_token": "fake-token", "expires_in": 3600}))
+                    }
+                }))
+                .route("/sheets/{sheet_id}/values/{range}", web::get().to(|path: web::Path<(String, String)>| async move {
+                    let (sheet_id, range) = path.into_inner();
+                    if sheet_id == "broken" {
+                        return HttpResponse::Forbidden().json(json!({"error": {"code": 403, "message": "The caller does not have permission"}}));
+                    }
+                    HttpResponse::Ok().json(json!({
+                        "range": range,
+                        "majorDimension": "ROWS",
+                        "values": [["Titre", "Artiste"], ["La Bohème", "Aznavour"]],
+                    }))
+                }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let address = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        format!("http://{}", address)
+    }
+
+    // Service account key file of one test, removed when dropped
+    struct FakeKey(std::path::PathBuf);
+
+    impl FakeKey {
+        fn write() -> FakeKey {
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            let key = openssl::rsa::Rsa::generate(2048).unwrap();
+            let pem = String::from_utf8(key.private_key_to_pem().unwrap()).unwrap();
+            let name = format!("karaoke-google-key-{}-{}.json", std::process::id(), NEXT.fetch_add(1, Ordering::SeqCst));
+            let path = std::env::temp_dir().join(name);
+            fs::write(&path, json!({"private_key": pem, "client_email": "karaoke@test"}).to_string()).unwrap();
+            FakeKey(path)
+        }
+
+        fn path(&self) -> String {
+            self.0.to_string_lossy().to_string()
+        }
+    }
+
+    impl Drop for FakeKey {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+
+    #[actix_web::test]
+    async fn test_fetch_sheet_reuses_token() {
+        let token_requests = Arc::new(AtomicUsize::new(0));
+        let base_url = start_fake_google(token_requests.clone()).await;
+        let key = FakeKey::write();
+        let client = GoogleClient::new(format!("{}/token", base_url), format!("{}/sheets/", base_url), Some(key.path()));
+
+        let sheet = client.fetch_sheet("catalog", "Feuille 1!A:D").await.unwrap();
+        assert_eq!(sheet.range, "Feuille 1!A:D");
+        assert_eq!(sheet.values.len(), 2);
+        client.fetch_sheet("catalog", "A:D").await.unwrap();
+        let sheets = client.fetch_sheets(&[
+            ("catalog".to_string(), "'Rock'!A:D".to_string()),
+            ("other".to_string(), "A:D".to_string()),
+        ]).await.unwrap();
+        assert_eq!(sheets.iter().map(|sheet| sheet.as_ref().unwrap().range.as_str()).collect::<Vec<&str>>(), vec!["'Rock'!A:D", "A:D"]);
+        assert_eq!(token_requests.load(Ordering::SeqCst), 1);
+
+        // The broken sheet fails alone
+        let sheets = client.fetch_sheets(&[("catalog".to_string(), "A:D".to_string()), ("broken".to_string(), "A:D".to_string())]).await.unwrap();
+        assert!(sheets[0].is_ok());
+        assert!(matches!(sheets[1], Err(GoogleError::Sheets { status: 403, .. })));
+
+        match client.fetch_sheet("broken", "A:D").await {
+            Err(GoogleError::Sheets { status, message }) => {
+                assert_eq!(status, 403);
+                assert_eq!(message, "The caller does not have permission");
+            }
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_sheet_without_key() {
+        let client = GoogleClient::new("http://127.0.0.1:9/token".to_string(), "http://127.0.0.1:9/sheets/".to_string(), None);
+        let error = client.fetch_sheet("catalog", "A:D").await.unwrap_err();
+        assert!(matches!(error, GoogleError::MissingKeyPath));
+        assert!(error.is_configuration());
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_sheet_with_invalid_url() {
+        let client = GoogleClient::new("http://127.0.0.1:9/token".to_string(), "pas une adresse".to_string(), None);
+        let error = client.fetch_sheet("catalog", "A:D").await.unwrap_err();
+        assert!(matches!(error, GoogleError::Config(_)));
+        assert_eq!(CatalogError::from(error).status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
 
 
     #[test]
@@ -144,14 +384,44 @@ mod tests {
             ],
         };
         let expected_result = vec![
-            Song{id:0,artist:"artiste 1".to_string(),title:"Chanson A".to_string(),lyrics_url:"test".to_string(),singer:None},
-            Song{id:0,artist:"artiste 2".to_string(),title:"Chanson B".to_string(),lyrics_url:"test 2".to_string(),singer:None},
-            Song{id:0,artist:"artiste 3".to_string(),title:"Chanson C".to_string(),lyrics_url:"test 3".to_string(),singer:None},
+            Song { lyrics_url: "Artist A".to_string(), ..Song::for_test(1, "artiste 1", "Chanson A") },
+            Song { lyrics_url: "Artist A".to_string(), ..Song::for_test(2, "artiste 2", "Chanson B") },
+            Song { lyrics_url: "Artist A".to_string(), ..Song::for_test(3, "artiste 3", "Chanson C") },
         ];
 
-        let songs = mock_sheet_data.transform_google_format_to_song();
+        let songs = mock_sheet_data.transform_google_format_to_song(&SheetLayout::default()).song_list();
         println!("{:?}", songs);
     
         assert!(songs == expected_result);
     }
+
+    #[test]
+    fn test_google_format_to_song_with_header_names() {
+        let mock_sheet_data = GoogleSheetResponse {
+            range: "Karaoké!A3:D6".to_string(),
+            majorDimension: "ROWS".to_string(),
+            values: vec![
+                vec!["Artiste".to_string(), "Année".to_string(), "Titre".to_string(), "Paroles".to_string()],
+                vec!["artiste 1".to_string(), "1984".to_string(), "Chanson A".to_string(), "http://paroles/a".to_string()],
+                vec!["artiste 2".to_string(), "1999".to_string(), "Chanson B".to_string()],
+                vec!["artiste 3".to_string()],
+            ],
+        };
+        let layout = SheetLayout {
+            range: "Karaoké!A:D".to_string(),
+            has_header: true,
+            title_column: "Titre".to_string(),
+            artist_column: "Artiste".to_string(),
+            lyrics_url_column: "Paroles".to_string(),
+            ..SheetLayout::default()
+        };
+        let expected_result = vec![
+            Song { lyrics_url: "http://paroles/a".to_string(), ..Song::for_test(1, "artiste 1", "Chanson A") },
+            Song::for_test(2, "artiste 2", "Chanson B"),
+        ];
+
+        let imported = mock_sheet_data.transform_google_format_to_song(&layout);
+        assert_eq!(imported.song_list(), expected_result);
+        assert_eq!(imported.skipped, vec![SkippedRow { row: 6, reason: SkipReason::MissingColumns, source: None }]);
+    }
 }
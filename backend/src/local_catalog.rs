@@ -0,0 +1,183 @@
+use actix_web::{web, post, Responder, HttpResponse};
+use actix_web::middleware::from_fn;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgConnection;
+
+use crate::auth::require_admin;
+use crate::catalog::{load_catalog, song_key};
+use crate::events::LiveEvent;
+use crate::song::Song;
+use crate::suggestion::SuggestionStatus;
+use crate::state::AppState;
+
+
+/// Source of the songs added by the maestro, shown next to the sheet names
+pub const LOCAL_SOURCE: &str = "local";
+
+// "Artist - Title", the dash needs spaces around it so "Jay-Z" stays whole
+const SEPARATORS: [&str; 5] = [" - ", " – ", " — ", " : ", " / "];
+// "Title by Artist"
+const REVERSED_SEPARATORS: [&str; 2] = [" by ", " par "];
+
+
+/// Artist and title guessed from the text of a suggestion, for the maestro to check
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub struct ProposedSong {
+    pub artist: String,
+    pub title: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct PromoteSuggestion {
+    pub suggestion_id: i32,
+    pub artist: String,
+    pub title: String,
+    #[serde(default)]
+    pub lyrics_url: String,
+}
+
+
+/// Add the song of a suggestion to the catalog and mark the suggestion as fulfilled
+#[post("/promote-suggestion", wrap = "from_fn(require_admin)")]
+async fn promote_suggestion(request: web::Json<PromoteSuggestion>, state: web::Data<AppState>) -> impl Responder {
+
+    let request = request.into_inner().trimmed();
+    if request.artist.is_empty() || request.title.is_empty() {
+        return HttpResponse::BadRequest().json(json!({
+            "status": "ko",
+            "content": "L'artiste et le titre sont obligatoires.",
+        }));
+    }
+
+    match request.promote(state).await {
+        Ok(Some(song)) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": song,
+        })),
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "status": "ko",
+            "content": "suggestion not found",
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+
+impl ProposedSong {
+    /// Only the first line is read, a suggestion without separator is taken as a title
+    pub fn parse(content: &str) -> ProposedSong {
+        let line = content.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+
+        let split = SEPARATORS
+            .iter()
+            .find_map(|separator| line.split_once(separator))
+            .or_else(|| {
+                REVERSED_SEPARATORS
+                    .iter()
+                    .find_map(|separator| line.split_once(separator))
+                    .map(|(title, artist)| (artist, title))
+            });
+
+        match split {
+            Some((artist, title)) => ProposedSong { artist: clean(artist), title: clean(title) },
+            None => ProposedSong { artist: String::new(), title: clean(line) },
+        }
+    }
+}
+
+// Without the quotes and spaces around
+fn clean(text: &str) -> String {
+    text.trim_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '«' | '»' | '“' | '”')).to_string()
+}
+
+impl PromoteSuggestion {
+    fn trimmed(self) -> PromoteSuggestion {
+        PromoteSuggestion {
+            artist: self.artist.trim().to_string(),
+            title: self.title.trim().to_string(),
+            lyrics_url: self.lyrics_url.trim().to_string(),
+            ..self
+        }
+    }
+
+    /// The catalog song, None when the suggestion does not exist.
+    /// A song already in the catalog is kept as it is, the suggestion is fulfilled anyway.
+    pub async fn promote(&self, state: web::Data<AppState>) -> Result<Option<Song>, sqlx::Error> {
+        let key = song_key(&self.artist, &self.title);
+        let mut tx = state.pool.begin().await?;
+
+        // Same lock as the imports, a refresh can't run in the middle
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('songs'))")
+            .execute(&mut *tx)
+            .await?;
+
+        let fulfilled = sqlx::query("UPDATE suggestions SET status = $1 WHERE id = $2")
+            .bind(SuggestionStatus::Fulfilled.as_str())
+            .bind(self.suggestion_id)
+            .execute(&mut *tx)
+            .await?;
+        if fulfilled.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        sqlx::query("INSERT INTO local_songs (song_key, artist, title, lyrics_url, suggestion_id) VALUES ($1, $2, $3, $4, $5)
+                     ON CONFLICT (song_key) DO UPDATE
+                     SET artist = EXCLUDED.artist, title = EXCLUDED.title, lyrics_url = EXCLUDED.lyrics_url, suggestion_id = EXCLUDED.suggestion_id")
+            .bind(&key)
+            .bind(&self.artist)
+            .bind(&self.title)
+            .bind(&self.lyrics_url)
+            .bind(self.suggestion_id)
+            .execute(&mut *tx)
+            .await?;
+        restore_local_songs(&mut tx, None).await?;
+
+        tx.commit().await?;
+
+        let catalog = load_catalog(&state.pool).await?;
+        let song = catalog.iter().find(|song| song_key(&song.artist, &song.title) == key).cloned();
+        state.update_playlist_cache(catalog);
+        state.notify(LiveEvent::Suggestions);
+        Ok(song)
+    }
+}
+
+/// Add the local songs missing from the active catalog, after an import deactivated them.
+/// A song also found in the sheets keeps the sheet version.
+pub async fn restore_local_songs(connection: &mut PgConnection, import_id: Option<i32>) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO songs (song_key, artist, title, lyrics_url, source, import_id, active)
+                 SELECT song_key, artist, title, lyrics_url, $1, $2, TRUE FROM local_songs
+                 ON CONFLICT (song_key) DO UPDATE
+                 SET artist = EXCLUDED.artist, title = EXCLUDED.title, lyrics_url = EXCLUDED.lyrics_url, source = EXCLUDED.source,
+                     genre = NULL, language = NULL, year = NULL, duet = FALSE, difficulty = NULL, duration = NULL,
+                     import_id = EXCLUDED.import_id, active = TRUE
+                 WHERE NOT songs.active")
+        .bind(LOCAL_SOURCE)
+        .bind(import_id)
+        .execute(connection)
+        .await?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proposed(artist: &str, title: &str) -> ProposedSong {
+        ProposedSong { artist: artist.to_string(), title: title.to_string() }
+    }
+
+    #[test]
+    fn test_parse_suggestion() {
+        assert_eq!(ProposedSong::parse("Daft Punk - Get Lucky"), proposed("Daft Punk", "Get Lucky"));
+        assert_eq!(ProposedSong::parse("  « Jay-Z » – Empire State of Mind\nsvp !"), proposed("Jay-Z", "Empire State of Mind"));
+        assert_eq!(ProposedSong::parse("La Bohème par Aznavour"), proposed("Aznavour", "La Bohème"));
+        assert_eq!(ProposedSong::parse("\"Bohemian Rhapsody\""), proposed("", "Bohemian Rhapsody"));
+        assert_eq!(ProposedSong::parse(""), proposed("", ""));
+    }
+}
@@ -0,0 +1,306 @@
+use actix_web::{web, get, post, Responder, HttpResponse};
+use actix_web::middleware::from_fn;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::FromRow;
+
+use crate::auth::require_admin;
+use crate::state::AppState;
+
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LyricsFormat {
+    Text,
+    /// Lines prefixed with their time, "[01:02.50]Paroles"
+    Lrc,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct LyricLine {
+    pub time_ms: i64,
+    pub text: String,
+}
+
+/// Lyrics of a song as shown by the viewer : local lyrics when there are some, the external link otherwise
+#[derive(Clone, PartialEq, Serialize, Debug)]
+pub struct Lyrics {
+    pub song_id: i32,
+    pub artist: String,
+    pub title: String,
+    pub format: Option<LyricsFormat>, // None without local lyrics
+    pub content: Option<String>, // As saved, to be edited by the maestro
+    pub lines: Vec<LyricLine>, // Only for LRC lyrics
+    pub url: Option<String>,
+    pub elapsed_ms: Option<i64>, // Time since the current song started, to follow LRC lyrics
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct LyricsRequest {
+    pub song_id: i32,
+    pub content: String, // Empty to delete the local lyrics
+    #[serde(default)]
+    pub format: Option<LyricsFormat>, // Detected when missing
+}
+
+#[derive(FromRow)]
+struct LyricsRow {
+    artist: String,
+    title: String,
+    lyrics_url: String,
+    format: Option<String>,
+    content: Option<String>,
+}
+
+
+#[get("/songs/{song_id}/lyrics")]
+async fn song_lyrics(path: web::Path<i32>, state: web::Data<AppState>) -> impl Responder {
+
+    match Lyrics::for_song(path.into_inner(), None, state).await {
+        Ok(Some(lyrics)) => HttpResponse::Ok().json(lyrics),
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "status": "ko",
+            "code": "unknown_song",
+            "content": "Cette chanson n'est pas dans la liste.",
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+// Lyrics of the song being performed, with the time elapsed since it started
+#[get("/current-lyrics")]
+async fn current_lyrics(state: web::Data<AppState>) -> impl Responder {
+
+    let current: Result<Option<(i32, Option<NaiveDateTime>, NaiveDateTime)>, sqlx::Error> =
+        sqlx::query_as("SELECT song_id, started_at, LOCALTIMESTAMP FROM current_playlist WHERE status = 'playing' LIMIT 1")
+            .fetch_optional(&state.pool)
+            .await;
+
+    let lyrics = match current {
+        Ok(Some((song_id, started_at, now))) => {
+            let elapsed_ms = started_at.map(|started_at| (now - started_at).num_milliseconds().max(0));
+            Lyrics::for_song(song_id, elapsed_ms, state).await
+        }
+        Ok(None) => Ok(None),
+        Err(error) => Err(error),
+    };
+
+    match lyrics {
+        Ok(Some(lyrics)) => HttpResponse::Ok().json(lyrics),
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "status": "ko",
+            "code": "nothing_playing",
+            "content": "Aucune chanson en cours.",
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+#[post("/change-lyrics", wrap = "from_fn(require_admin)")]
+async fn change_lyrics(request: web::Json<LyricsRequest>, state: web::Data<AppState>) -> impl Responder {
+
+    match request.save(state.clone()).await {
+        Ok(true) => match Lyrics::for_song(request.song_id, None, state).await {
+            Ok(lyrics) => HttpResponse::Ok().json(json!({
+                "status": "ok",
+                "content": lyrics,
+            })),
+            Err(error) => HttpResponse::InternalServerError().json(json!({
+                "status": "ko",
+                "content": error.to_string(),
+            })),
+        },
+        Ok(false) => HttpResponse::NotFound().json(json!({
+            "status": "ko",
+            "code": "unknown_song",
+            "content": "Cette chanson n'est pas dans la liste.",
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+
+impl Lyrics {
+    /// None when the song does not exist, inactive songs still have their lyrics for the queue history
+    pub async fn for_song(song_id: i32, elapsed_ms: Option<i64>, state: web::Data<AppState>) -> Result<Option<Lyrics>, sqlx::Error> {
+        let row: Option<LyricsRow> = sqlx::query_as(
+            "SELECT s.artist, s.title, s.lyrics_url, l.format, l.content
+             FROM songs s LEFT JOIN song_lyrics l ON l.song_id = s.id
+             WHERE s.id = $1")
+            .bind(song_id)
+            .fetch_optional(&state.pool)
+            .await?;
+
+        Ok(row.map(|row| {
+            let format = row.format.as_deref().map(LyricsFormat::from_str);
+            Lyrics {
+                song_id,
+                artist: row.artist,
+                title: row.title,
+                lines: match (format, &row.content) {
+                    (Some(LyricsFormat::Lrc), Some(content)) => parse_lrc(content),
+                    _ => vec![],
+                },
+                format,
+                content: row.content,
+                url: Some(row.lyrics_url).filter(|url| !url.trim().is_empty()),
+                elapsed_ms,
+            }
+        }))
+    }
+}
+
+impl LyricsRequest {
+    /// False when the song does not exist
+    pub async fn save(&self, state: web::Data<AppState>) -> Result<bool, sqlx::Error> {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM songs WHERE id = $1)")
+            .bind(self.song_id)
+            .fetch_one(&state.pool)
+            .await?;
+        if !exists {
+            return Ok(false);
+        }
+
+        if self.content.trim().is_empty() {
+            sqlx::query("DELETE FROM song_lyrics WHERE song_id = $1")
+                .bind(self.song_id)
+                .execute(&state.pool)
+                .await?;
+            return Ok(true);
+        }
+
+        let format = self.format.unwrap_or_else(|| LyricsFormat::detect(&self.content));
+        sqlx::query("INSERT INTO song_lyrics (song_id, format, content) VALUES ($1, $2, $3)
+                     ON CONFLICT (song_id) DO UPDATE SET format = EXCLUDED.format, content = EXCLUDED.content, updated_at = CURRENT_TIMESTAMP")
+            .bind(self.song_id)
+            .bind(format.as_str())
+            .bind(&self.content)
+            .execute(&state.pool)
+            .await?;
+        Ok(true)
+    }
+}
+
+
+impl LyricsFormat {
+    /// LRC as soon as a line starts with a time
+    pub fn detect(content: &str) -> LyricsFormat {
+        if content.lines().any(|line| !line_times(line.trim()).0.is_empty()) {
+            LyricsFormat::Lrc
+        } else {
+            LyricsFormat::Text
+        }
+    }
+
+    fn from_str(value: &str) -> LyricsFormat {
+        match value {
+            "lrc" => LyricsFormat::Lrc,
+            _ => LyricsFormat::Text,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            LyricsFormat::Text => "text",
+            LyricsFormat::Lrc => "lrc",
+        }
+    }
+}
+
+
+/// Timed lines of LRC lyrics, sorted by time. A line can have several times ("[00:12.00][01:40.00]Refrain"),
+/// the `[offset:+500]` tag shifts every line and the other tags are ignored.
+pub fn parse_lrc(content: &str) -> Vec<LyricLine> {
+    let mut offset_ms = 0;
+    let mut lines = vec![];
+
+    for line in content.lines().map(|line| line.trim()) {
+        if let Some(offset) = line.strip_prefix("[offset:").and_then(|rest| rest.strip_suffix(']')) {
+            offset_ms = offset.trim().parse().unwrap_or(0);
+            continue;
+        }
+        let (times, text) = line_times(line);
+        for time_ms in times {
+            lines.push(LyricLine { time_ms, text: text.trim().to_string() });
+        }
+    }
+
+    // A positive offset shows the lines sooner
+    for line in lines.iter_mut() {
+        line.time_ms = line.time_ms.saturating_sub(offset_ms).max(0);
+    }
+    lines.sort_by_key(|line| line.time_ms);
+    lines
+}
+
+// The times at the start of a line and the rest of it
+fn line_times(line: &str) -> (Vec<i64>, &str) {
+    let mut times = vec![];
+    let mut rest = line;
+    while let Some((tag, after)) = rest.strip_prefix('[').and_then(|tag| tag.split_once(']')) {
+        match parse_time(tag) {
+            Some(time_ms) => times.push(time_ms),
+            None => break,
+        }
+        rest = after;
+    }
+    (times, rest)
+}
+
+// "01:02.50" or "01:02" -> milliseconds
+fn parse_time(tag: &str) -> Option<i64> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: i64 = minutes.trim().parse().ok()?;
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, "0"));
+    let seconds: i64 = seconds.parse().ok()?;
+    if !(0..60).contains(&seconds) || fraction.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // ".5", ".50" and ".500" are all half a second
+    let fraction_ms: i64 = format!("{:0<3}", &fraction[..fraction.len().min(3)]).parse().ok()?;
+    minutes.checked_mul(60_000)?.checked_add(seconds * 1000 + fraction_ms)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lrc() {
+        let content = "[ar:Stromae]\n[offset:+200]\n[00:12.50]Alors on danse\n\n[00:05.1][01:00.00]Qui dit étude dit travail\nSans temps";
+
+        assert_eq!(parse_lrc(content), vec![
+            LyricLine { time_ms: 4900, text: "Qui dit étude dit travail".to_string() },
+            LyricLine { time_ms: 12300, text: "Alors on danse".to_string() },
+            LyricLine { time_ms: 59800, text: "Qui dit étude dit travail".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(LyricsFormat::detect("[ti:La Bohème]\n[00:01.00]Je vous parle"), LyricsFormat::Lrc);
+        assert_eq!(LyricsFormat::detect("Je vous parle d'un temps\n[Refrain]"), LyricsFormat::Text);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("01:02.5"), Some(62500));
+        assert_eq!(parse_time("00:02.123"), Some(2123));
+        assert_eq!(parse_time("3:07"), Some(187000));
+        assert_eq!(parse_time("ar:Stromae"), None);
+        assert_eq!(parse_time("00:75.00"), None);
+        assert_eq!(parse_time("00:-9223372036854775807"), None);
+        assert_eq!(parse_time("9223372036854775807:00"), None);
+    }
+}
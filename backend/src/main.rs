@@ -8,13 +8,21 @@ use actix_web::web;
 use actix_web::http::header::HeaderValue;
 use actix_web::dev::RequestHead;
 use actix_web::App;
-use std::sync::{Arc, RwLock};
 use crate::state::AppState;
 use sqlx::PgPool;
-use crate::song::{add_song, song_update, song_playlist, delete_song, song_data};
-use crate::suggestion::add_suggestion;
+use crate::song::{add_song, song_update, song_playlist, delete_song, move_song, next_song, song_data};
+use crate::suggestion::{add_suggestion, requested_songs, vote_suggestion, suggestion_inbox, change_suggestion_status, delete_suggestion};
 use crate::content::{add_content, get_content};
 use crate::config::{change_config, get_config};
+use crate::auth::{login, auth_check};
+use crate::events::live_events;
+use crate::sheet_layout::{get_sheet_layout, change_sheet_layout};
+use crate::catalog::{catalog_upload, MAX_CATALOG_FILE_SIZE};
+use crate::catalog_refresh::catalog_refresh_status;
+use crate::song_search::search_songs;
+use crate::lyrics::{song_lyrics, current_lyrics, change_lyrics};
+use crate::qr_code::join_qr_code;
+use crate::local_catalog::promote_suggestion;
 
 
 
@@ -24,6 +32,21 @@ mod content;
 mod state;
 mod suggestion;
 mod config;
+mod auth;
+mod events;
+mod queue_order;
+mod queue_rules;
+mod sheet_layout;
+mod catalog;
+mod catalog_diff;
+mod catalog_refresh;
+mod song_search;
+mod catalog_page;
+mod song_metadata;
+mod lyrics;
+mod qr_code;
+mod local_catalog;
+mod moderation;
 
 
 fn get_database_url() -> String {
@@ -63,11 +86,26 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to run migrations");
 
+    // Songs saved before the keys existed must be matched by the first import
+    catalog::backfill_song_keys(&pgpool)
+        .await
+        .expect("Failed to backfill the song keys");
+
+    auth::ensure_admin_user(&pgpool)
+        .await
+        .expect("Failed to create the admin user");
 
-    let state: web::Data<AppState> = web::Data::new(AppState { 
-        playlist_cache: Arc::new(RwLock::new(vec![])),
-        pool: pgpool, 
-    });
+    let state: web::Data<AppState> = web::Data::new(AppState::new(pgpool));
+
+    // The site keeps working with the last imported songs when the catalog source is unreachable
+    match catalog::load_catalog(&state.pool).await {
+        Ok(songs) => {
+            println!("{} songs loaded from the catalog", songs.len());
+            state.update_playlist_cache(songs);
+        }
+        Err(err) => eprintln!("Failed to load the song catalog: {}", err),
+    }
+    catalog_refresh::spawn_catalog_refresh(state.clone()).await;
 
 
 
@@ -83,26 +121,50 @@ async fn main() -> std::io::Result<()> {
             }
         })
         .allowed_methods(vec!["GET", "POST"]) // Restrict to needed methods
-        .allowed_headers(vec!["Content-Type", "Authorization"]) // Only necessary headers
+        .allowed_headers(vec!["Content-Type", "Authorization", moderation::CLIENT_ID_HEADER]) // Only necessary headers
         .max_age(3600);
 
         App::new()
             .app_data(state.clone())
             .service(
                 web::scope("/api")
-                    .wrap(Arc::new(cors))
+                    .wrap(cors)
                     .service(song_data)
-                    .service(song_update)
+                    .service(search_songs)
+                    .service(song_lyrics)
+                    .service(current_lyrics)
+                    .service(join_qr_code)
                     .service(add_song)
                     .service(song_playlist)
-                    .service(delete_song)
                     .service(add_suggestion)
-                    .service(add_content)
+                    .service(requested_songs)
+                    .service(vote_suggestion)
                     .service(get_content)
-                    .service(change_config)
                     .service(get_config)
+                    .service(login)
+                    .service(live_events)
+                    // admin only routes, each one is wrapped with require_admin
+                    .service(song_update)
+                    .service(delete_song)
+                    .service(move_song)
+                    .service(next_song)
+                    .service(add_content)
+                    .service(change_config)
+                    .service(get_sheet_layout)
+                    .service(change_sheet_layout)
+                    .service(catalog_upload)
+                    .service(catalog_refresh_status)
+                    .service(change_lyrics)
+                    .service(suggestion_inbox)
+                    .service(change_suggestion_status)
+                    .service(delete_suggestion)
+                    .service(promote_suggestion)
+                    .service(auth_check)
+                    // uploaded catalog files are bigger than the default body limit, only the upload reads a raw body
+                    .app_data(web::PayloadConfig::new(MAX_CATALOG_FILE_SIZE))
             )
         .service(Files::new("/maestro", "public").index_file("index.html"))
+        .service(Files::new("/tv", "public").index_file("index.html"))
         .service(Files::new("/", "public").index_file("index.html"))
     })
     .bind(("0.0.0.0", port))?
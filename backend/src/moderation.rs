@@ -0,0 +1,423 @@
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::middleware::Next;
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use crate::catalog::normalize;
+use crate::config::get_config_value;
+use crate::state::AppState;
+
+
+// Sent by the frontend, every guest of the venue shares the same address
+pub const CLIENT_ID_HEADER: &str = "X-Client-Id";
+const MAX_CLIENT_ID_LENGTH: usize = 64;
+
+// Requests allowed on each limited route during the window
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+const MAX_REQUESTS_PER_CLIENT: usize = 5;
+const MAX_REQUESTS_PER_ADDRESS: usize = 60;
+// Keys are only swept once there are that many of them
+const MAX_TRACKED_KEYS: usize = 1000;
+
+pub const MAX_SINGER_LENGTH: usize = 40;
+pub const MAX_SONG_FIELD_LENGTH: usize = 150;
+pub const MAX_COMMENT_LENGTH: usize = 1000;
+
+
+/// Recent requests of each client on the limited routes
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    hits: HashMap<String, VecDeque<Instant>>,
+}
+
+/// Proxies allowed to tell the address of the guest in X-Forwarded-For, from TRUSTED_PROXIES
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum TrustedProxies {
+    /// The header is ignored, anybody can write it
+    #[default]
+    None,
+    /// The server is only reachable through one proxy, whatever its address
+    Any,
+    Addresses(Vec<IpAddr>),
+}
+
+/// Reason why a field sent by a guest is refused, shown to the guest
+#[derive(Clone, PartialEq, Debug)]
+pub enum InvalidInput {
+    Empty,
+    TooLong { field: &'static str, max: usize },
+    InvalidCharacters { field: &'static str },
+    BannedWord { field: &'static str },
+}
+
+/// Checks of the text sent by the guests, the banned words come from the config table
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Moderation {
+    pub banned_words: Vec<String>, // Normalized, see catalog::normalize
+}
+
+
+/// Middleware limiting the requests of each client and of each address on a route.
+/// The address is the one of the connection, or the one given by a trusted proxy.
+pub async fn rate_limit<B: MessageBody + 'static>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+
+    let path = req.path().to_string();
+    let client = client_id(req.request());
+
+    let retry_after = req.app_data::<web::Data<AppState>>().and_then(|state| {
+        let address = request_address(req.request(), &state.trusted_proxies);
+        let mut limiter = state.rate_limiter.lock().unwrap();
+        limiter.hit(&[
+            (format!("{}|{}|{}", path, address, client), MAX_REQUESTS_PER_CLIENT),
+            (format!("{}|{}", path, address), MAX_REQUESTS_PER_ADDRESS),
+        ], Instant::now())
+    });
+
+    match retry_after {
+        None => next.call(req).await.map(|res| res.map_into_left_body()),
+        Some(retry_after) => {
+            let seconds = retry_after.as_secs().max(1);
+            let response = HttpResponse::TooManyRequests()
+                .insert_header((RETRY_AFTER, seconds.to_string()))
+                .json(json!({
+                    "status": "ko",
+                    "code": "rate_limited",
+                    "content": format!("Trop de demandes, réessayez dans {} secondes.", seconds),
+                }));
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
+
+/// Id the browser of the guest sends with its requests, empty when missing
+fn client_id(req: &HttpRequest) -> String {
+    req.headers()
+        .get(CLIENT_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().chars().take(MAX_CLIENT_ID_LENGTH).collect())
+        .unwrap_or_default()
+}
+
+fn request_address(req: &HttpRequest, proxies: &TrustedProxies) -> String {
+    let forwarded_for: Vec<&str> = req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    client_address(req.peer_addr().map(|peer| peer.ip()), &forwarded_for.join(","), proxies)
+}
+
+/// Guest who votes for a suggestion: the id of its browser, or its address without one
+pub fn voter_id(req: &HttpRequest, proxies: &TrustedProxies) -> String {
+    match client_id(req) {
+        client if client.is_empty() => format!("address {}", request_address(req, proxies)),
+        client => format!("client {}", client),
+    }
+}
+
+/// Address of the guest: the peer of the connection, or the last address its trusted proxies
+/// added to X-Forwarded-For. The first entries of the header come from the guest itself.
+pub fn client_address(peer: Option<IpAddr>, forwarded_for: &str, proxies: &TrustedProxies) -> String {
+    let Some(peer) = peer else {
+        return String::new();
+    };
+    if !proxies.trusts(peer) {
+        return peer.to_string();
+    }
+
+    let mut address = peer;
+    for entry in forwarded_for.split(',').rev() {
+        match entry.trim().parse::<IpAddr>() {
+            Ok(forwarded) => address = forwarded,
+            Err(_) => break,
+        }
+        if *proxies == TrustedProxies::Any || !proxies.trusts(address) {
+            break;
+        }
+    }
+    address.to_string()
+}
+
+/// 400 response for a refused field
+pub fn bad_request(invalid: &InvalidInput) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({
+        "status": "ko",
+        "code": invalid.code(),
+        "content": invalid.message(),
+    }))
+}
+
+
+impl TrustedProxies {
+    /// Addresses separated by commas, or "*"
+    pub fn from_value(value: Option<String>) -> TrustedProxies {
+        match value.as_deref().map(str::trim) {
+            None | Some("") => TrustedProxies::None,
+            Some("*") => TrustedProxies::Any,
+            Some(value) => TrustedProxies::Addresses(
+                value.split(',').filter_map(|address| address.trim().parse().ok()).collect()
+            ),
+        }
+    }
+
+    pub fn from_env() -> TrustedProxies {
+        TrustedProxies::from_value(std::env::var("TRUSTED_PROXIES").ok())
+    }
+
+    pub fn trusts(&self, address: IpAddr) -> bool {
+        match self {
+            TrustedProxies::None => false,
+            TrustedProxies::Any => true,
+            TrustedProxies::Addresses(addresses) => addresses.contains(&address),
+        }
+    }
+}
+
+
+impl RateLimiter {
+    /// Count a request against each (key, max) limit, or the time left before the next one is allowed
+    /// when one of them is reached. A refused request is counted against none of them.
+    pub fn hit(&mut self, limits: &[(String, usize)], now: Instant) -> Option<Duration> {
+        if self.hits.len() >= MAX_TRACKED_KEYS {
+            self.hits.retain(|_, hits| hits.back().is_some_and(|last| now.duration_since(*last) < RATE_LIMIT_WINDOW));
+        }
+
+        let mut retry_after = None;
+        for (key, max) in limits {
+            let hits = self.hits.entry(key.clone()).or_default();
+            while hits.front().is_some_and(|first| now.duration_since(*first) >= RATE_LIMIT_WINDOW) {
+                hits.pop_front();
+            }
+            if let Some(first) = hits.front().filter(|_| hits.len() >= *max) {
+                retry_after = retry_after.max(Some(RATE_LIMIT_WINDOW - now.duration_since(*first)));
+            }
+        }
+
+        if retry_after.is_none() {
+            for (key, _) in limits {
+                self.hits.entry(key.clone()).or_default().push_back(now);
+            }
+        }
+        retry_after
+    }
+}
+
+
+impl InvalidInput {
+    pub fn code(&self) -> &'static str {
+        match self {
+            InvalidInput::Empty => "empty",
+            InvalidInput::TooLong { .. } => "too_long",
+            InvalidInput::InvalidCharacters { .. } => "invalid_characters",
+            InvalidInput::BannedWord { .. } => "banned_word",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            InvalidInput::Empty => "Indiquez un titre ou un message.".to_string(),
+            InvalidInput::TooLong { field, max } => format!("{} ne doit pas dépasser {} caractères.", field, max),
+            InvalidInput::InvalidCharacters { field } => format!("{} contient des caractères non autorisés.", field),
+            InvalidInput::BannedWord { field } => format!("{} contient un mot interdit.", field),
+        }
+    }
+}
+
+
+impl Moderation {
+    /// Words separated by commas or line breaks
+    pub fn from_value(banned_words: Option<String>) -> Moderation {
+        Moderation {
+            banned_words: banned_words
+                .unwrap_or_default()
+                .split([',', '\n'])
+                .map(normalize)
+                .filter(|word| !word.is_empty())
+                .collect(),
+        }
+    }
+
+    pub async fn load(state: web::Data<AppState>) -> Result<Moderation, sqlx::Error> {
+        Ok(Moderation::from_value(get_config_value("banned_words", state).await?))
+    }
+
+    /// Only the comments can span several lines
+    pub fn check(&self, field: &'static str, value: &str, max: usize, multiline: bool) -> Result<(), InvalidInput> {
+        let value = value.trim();
+
+        if value.chars().count() > max {
+            return Err(InvalidInput::TooLong { field, max });
+        }
+
+        let is_allowed = |c: char| match c {
+            '\n' | '\r' | '\t' => multiline,
+            // Bidirectional overrides can disguise the text
+            '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => false,
+            c => !c.is_control(),
+        };
+        if !value.chars().all(is_allowed) {
+            return Err(InvalidInput::InvalidCharacters { field });
+        }
+
+        // Whole words, whatever the case, accents or punctuation around them
+        let words = format!(" {} ", normalize(value));
+        if self.banned_words.iter().any(|banned| words.contains(&format!(" {} ", banned))) {
+            return Err(InvalidInput::BannedWord { field });
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{post, App, HttpRequest, Responder};
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+    use serde::Deserialize;
+    use sqlx::PgPool;
+    use std::fs;
+    use std::path::Path;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    #[allow(non_snake_case)]
+    struct Secrets {
+        pub DATABASE_URL: String,
+    }
+
+    fn load_secrets() -> Secrets {
+        let content = fs::read_to_string(Path::new("Secrets.toml"))
+            .expect("Failed to read Secrets.toml");
+        toml::from_str(&content)
+            .expect("Failed to parse Secrets.toml")
+    }
+
+    #[post("/limited", wrap = "from_fn(rate_limit)")]
+    async fn limited(_req: HttpRequest) -> impl Responder {
+        HttpResponse::Ok().finish()
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+
+        let guest = |name: &str| [(name.to_string(), 3), ("venue".to_string(), 5)];
+
+        for _ in 0..3 {
+            assert_eq!(limiter.hit(&guest("guest"), start), None);
+        }
+        let retry_after = limiter.hit(&guest("guest"), start + Duration::from_secs(20));
+        assert_eq!(retry_after, Some(Duration::from_secs(40)));
+        for _ in 0..2 {
+            assert_eq!(limiter.hit(&guest("other guest"), start + Duration::from_secs(30)), None);
+        }
+
+        // The address is full, the refused request is not counted for the guest
+        assert_eq!(limiter.hit(&guest("third guest"), start + Duration::from_secs(30)), Some(Duration::from_secs(30)));
+        assert_eq!(limiter.hit(&guest("third guest"), start + RATE_LIMIT_WINDOW), None);
+        assert_eq!(limiter.hits["third guest"].len(), 1);
+
+        // The window slides
+        assert_eq!(limiter.hit(&guest("guest"), start + RATE_LIMIT_WINDOW), None);
+    }
+
+    #[test]
+    fn test_client_address() {
+        let peer: Option<IpAddr> = Some("10.0.0.1".parse().unwrap());
+        let proxies = TrustedProxies::from_value(Some("10.0.0.1, 10.0.0.2".to_string()));
+        assert_eq!(proxies, TrustedProxies::Addresses(vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()]));
+
+        assert_eq!(client_address(peer, "1.2.3.4", &TrustedProxies::None), "10.0.0.1");
+        assert_eq!(client_address(peer, "6.6.6.6, 1.2.3.4, 10.0.0.2", &proxies), "1.2.3.4");
+        assert_eq!(client_address(peer, "", &proxies), "10.0.0.1");
+        assert_eq!(client_address(peer, "6.6.6.6, 1.2.3.4", &TrustedProxies::Any), "1.2.3.4");
+        assert_eq!(client_address(Some("9.9.9.9".parse().unwrap()), "1.2.3.4", &proxies), "9.9.9.9");
+    }
+
+    #[test]
+    fn test_voter_id() {
+        let req = TestRequest::default().peer_addr("10.0.0.1:1234".parse().unwrap()).insert_header((CLIENT_ID_HEADER, " guest 1 ")).to_http_request();
+        assert_eq!(voter_id(&req, &TrustedProxies::None), "client guest 1");
+        let req = TestRequest::default().peer_addr("10.0.0.1:1234".parse().unwrap()).to_http_request();
+        assert_eq!(voter_id(&req, &TrustedProxies::None), "address 10.0.0.1");
+    }
+
+    #[test]
+    fn test_moderation_check() {
+        let moderation = Moderation::from_value(Some("Gros mot, très  vilain\nzut".to_string()));
+        assert_eq!(moderation.banned_words, vec!["gros mot", "tres vilain", "zut"]);
+
+        assert_eq!(moderation.check("Le nom", " Zoé ", MAX_SINGER_LENGTH, false), Ok(()));
+        assert_eq!(moderation.check("Le nom", &"a".repeat(41), MAX_SINGER_LENGTH, false),
+            Err(InvalidInput::TooLong { field: "Le nom", max: 40 }));
+        assert_eq!(moderation.check("Le nom", "Zoé\nZoé", MAX_SINGER_LENGTH, false),
+            Err(InvalidInput::InvalidCharacters { field: "Le nom" }));
+        assert_eq!(moderation.check("Le message", "Merci !\nZoé", MAX_COMMENT_LENGTH, true), Ok(()));
+        assert_eq!(moderation.check("Le nom", "abc\u{202E}def", MAX_SINGER_LENGTH, false),
+            Err(InvalidInput::InvalidCharacters { field: "Le nom" }));
+        assert_eq!(moderation.check("Le nom", "ZUT !", MAX_SINGER_LENGTH, false),
+            Err(InvalidInput::BannedWord { field: "Le nom" }));
+        assert_eq!(moderation.check("Le nom", "Très-Vilain", MAX_SINGER_LENGTH, false),
+            Err(InvalidInput::BannedWord { field: "Le nom" }));
+        // Only whole words
+        assert_eq!(moderation.check("Le titre", "Zutopia", MAX_SONG_FIELD_LENGTH, false), Ok(()));
+    }
+
+    #[actix_web::test]
+    async fn test_rate_limit_middleware() {
+        let pool = PgPool::connect_lazy(&load_secrets().DATABASE_URL).unwrap();
+        let app = init_service(App::new()
+            .app_data(web::Data::new(AppState::new(pool)))
+            .service(limited)).await;
+
+        let request = |client: &str| TestRequest::post()
+            .uri("/limited")
+            .insert_header((CLIENT_ID_HEADER, client.to_string()))
+            .to_request();
+
+        for _ in 0..MAX_REQUESTS_PER_CLIENT {
+            assert!(call_service(&app, request("guest")).await.status().is_success());
+        }
+        let response = call_service(&app, request("guest")).await;
+        assert_eq!(response.status(), 429);
+        assert!(response.headers().contains_key(RETRY_AFTER));
+        let body: serde_json::Value = read_body_json(response).await;
+        assert_eq!(body["code"], "rate_limited");
+
+        // Another guest behind the same address is not blocked
+        assert!(call_service(&app, request("other guest")).await.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn test_rate_limit_spoofed_headers() {
+        let pool = PgPool::connect_lazy(&load_secrets().DATABASE_URL).unwrap();
+        let app = init_service(App::new()
+            .app_data(web::Data::new(AppState::new(pool)))
+            .service(limited)).await;
+
+        // A new client id and a new forwarded address on every request, from one connection address
+        let request = |index: usize| TestRequest::post()
+            .uri("/limited")
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .insert_header((CLIENT_ID_HEADER, format!("guest {}", index)))
+            .insert_header(("X-Forwarded-For", format!("1.2.3.{}", index % 250)))
+            .to_request();
+
+        for index in 0..MAX_REQUESTS_PER_ADDRESS {
+            assert!(call_service(&app, request(index)).await.status().is_success());
+        }
+        assert_eq!(call_service(&app, request(MAX_REQUESTS_PER_ADDRESS)).await.status(), 429);
+    }
+}
@@ -0,0 +1,146 @@
+use std::io::Cursor;
+use actix_web::{web, get, http::header, HttpRequest, Responder, HttpResponse};
+use image::{ImageFormat, Luma};
+use qrcode::QrCode;
+use qrcode::render::svg;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config::get_config_value;
+use crate::moderation::TrustedProxies;
+use crate::state::AppState;
+
+
+// Width of the QR code in pixels when the request does not say, and its limits
+const DEFAULT_SIZE: u32 = 300;
+const MIN_SIZE: u32 = 100;
+const MAX_SIZE: u32 = 2000;
+
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QrFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+#[derive(Deserialize, Debug)]
+struct QrCodeQuery {
+    #[serde(default)]
+    format: QrFormat,
+    size: Option<u32>,
+}
+
+
+/// QR code of the address guests open to join, to print or project
+#[get("/qr-code")]
+async fn join_qr_code(req: HttpRequest, query: web::Query<QrCodeQuery>, state: web::Data<AppState>) -> impl Responder {
+
+    let url = match get_config_value("public_url", state.clone()).await {
+        Ok(value) => public_url(value, &req, &state.trusted_proxies),
+        Err(error) => return HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    };
+
+    let size = query.size.unwrap_or(DEFAULT_SIZE).clamp(MIN_SIZE, MAX_SIZE);
+    match render(&url, query.format, size) {
+        Ok(image) => HttpResponse::Ok()
+            .content_type(match query.format {
+                QrFormat::Svg => "image/svg+xml",
+                QrFormat::Png => "image/png",
+            })
+            // The address can be changed at any time from the admin page
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .body(image),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error,
+        })),
+    }
+}
+
+
+// The configured address, or the address of this server which also serves the site.
+// The forwarded host and scheme headers are only read from a trusted proxy, anybody can write them.
+fn public_url(configured: Option<String>, req: &HttpRequest, proxies: &TrustedProxies) -> String {
+    if let Some(url) = configured.map(|url| url.trim().to_string()).filter(|url| !url.is_empty()) {
+        return url;
+    }
+
+    if req.peer_addr().is_some_and(|peer| proxies.trusts(peer.ip())) {
+        let info = req.connection_info();
+        return format!("{}://{}/", info.scheme(), info.host());
+    }
+    let config = req.app_config();
+    let host = req
+        .uri()
+        .authority()
+        .map(|authority| authority.to_string())
+        .or_else(|| req.headers().get(header::HOST).and_then(|host| host.to_str().ok()).map(str::to_string))
+        .unwrap_or_else(|| config.host().to_string());
+    format!("{}://{}/", if config.secure() { "https" } else { "http" }, host)
+}
+
+/// The QR code of `url`, at least `size` pixels wide
+pub fn render(url: &str, format: QrFormat, size: u32) -> Result<Vec<u8>, String> {
+    let code = QrCode::new(url.as_bytes()).map_err(|err| format!("Adresse trop longue pour un QR code : {}", err))?;
+
+    match format {
+        QrFormat::Svg => Ok(code
+            .render::<svg::Color>()
+            .min_dimensions(size, size)
+            .build()
+            .into_bytes()),
+        QrFormat::Png => {
+            let image = code.render::<Luma<u8>>().min_dimensions(size, size).build();
+            let mut png = Cursor::new(Vec::new());
+            image.write_to(&mut png, ImageFormat::Png).map_err(|err| err.to_string())?;
+            Ok(png.into_inner())
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_render() {
+        let svg = String::from_utf8(render("https://karaoke.example/", QrFormat::Svg, 200).unwrap()).unwrap();
+        assert!(svg.contains("<svg"));
+
+        let png = render("https://karaoke.example/", QrFormat::Png, 200).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let decoded = image::load_from_memory(&png).unwrap();
+        assert!(decoded.width() >= 200);
+    }
+
+    #[test]
+    fn test_public_url() {
+        let proxies = TrustedProxies::from_value(Some("10.0.0.1".to_string()));
+        let req = TestRequest::default()
+            .insert_header((header::HOST, "karaoke.local:8080"))
+            .peer_addr("10.0.0.1:40000".parse().unwrap())
+            .to_http_request();
+
+        assert_eq!(public_url(Some(" https://carpe.example/ ".to_string()), &req, &proxies), "https://carpe.example/");
+        assert_eq!(public_url(Some("".to_string()), &req, &proxies), "http://karaoke.local:8080/");
+        assert_eq!(public_url(None, &req, &proxies), "http://karaoke.local:8080/");
+
+        // The forwarded host only counts when a trusted proxy gives it
+        let forwarded = |peer: &str| TestRequest::default()
+            .insert_header((header::HOST, "karaoke.local:8080"))
+            .insert_header(("X-Forwarded-Host", "karaoke.example"))
+            .insert_header(("X-Forwarded-Proto", "https"))
+            .peer_addr(peer.parse().unwrap())
+            .to_http_request();
+        assert_eq!(public_url(None, &forwarded("10.0.0.1:40000"), &proxies), "https://karaoke.example/");
+        assert_eq!(public_url(None, &forwarded("6.6.6.6:40000"), &proxies), "http://karaoke.local:8080/");
+        assert_eq!(public_url(None, &forwarded("10.0.0.1:40000"), &TrustedProxies::None), "http://karaoke.local:8080/");
+    }
+}
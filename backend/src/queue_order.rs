@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use actix_web::web;
+
+use crate::config::get_config_value;
+use crate::song::Song;
+use crate::state::AppState;
+
+
+/// Policy deciding in which order the queued songs are performed, stored in the `queue_order` config
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum QueueOrder {
+    /// First come, first served (following the maestro's manual positions)
+    Fifo,
+    /// Every singer gets a turn before anyone sings twice
+    RoundRobin,
+}
+
+impl QueueOrder {
+    pub fn from_config(value: &str) -> QueueOrder {
+        match value {
+            "round_robin" => QueueOrder::RoundRobin,
+            _ => QueueOrder::Fifo,
+        }
+    }
+
+    /// Policy currently configured, FIFO when not set
+    pub async fn current(state: web::Data<AppState>) -> Result<QueueOrder, sqlx::Error> {
+        let value = get_config_value("queue_order", state).await?;
+        Ok(QueueOrder::from_config(value.as_deref().unwrap_or_default()))
+    }
+
+    /// The maestro's positions only decide the order in FIFO
+    pub fn allows_manual_moves(&self) -> bool {
+        *self == QueueOrder::Fifo
+    }
+
+    /// Reorder a playlist sorted by position, the song being performed stays first
+    pub fn apply(&self, songs: Vec<Song>) -> Vec<Song> {
+        match self {
+            QueueOrder::Fifo => songs,
+            QueueOrder::RoundRobin => round_robin(songs),
+        }
+    }
+}
+
+
+// Entries without singer (jukebox mode) are never grouped together
+fn singer_key(song: &Song) -> String {
+    match song.singer.as_deref().map(str::trim) {
+        Some(singer) if !singer.is_empty() => singer.to_lowercase(),
+        _ => format!("#{}", song.id),
+    }
+}
+
+/// Interleave singers : the n-th song of every singer comes before anyone's (n+1)-th song.
+/// Within a round, singers keep the order of their song positions.
+fn round_robin(songs: Vec<Song>) -> Vec<Song> {
+    let mut songs_per_singer: HashMap<String, usize> = HashMap::new();
+
+    let mut rounds: Vec<(usize, usize, Song)> = songs
+        .into_iter()
+        .enumerate()
+        .map(|(index, song)| {
+            let count = songs_per_singer.entry(singer_key(&song)).or_insert(0);
+            // The song being performed counts as its singer's turn but stays on top
+            let round = if song.is_playing() { 0 } else { *count + 1 };
+            *count += 1;
+            (round, index, song)
+        })
+        .collect();
+
+    rounds.sort_by_key(|(round, index, _)| (*round, *index));
+    rounds.into_iter().map(|(_, _, song)| song).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(id: i32, singer: &str) -> Song {
+        Song {
+            singer: Some(singer.to_string()),
+            status: Some("queued".to_string()),
+            ..Song::for_test(id, "artiste", &format!("Chanson {}", id))
+        }
+    }
+
+    fn ids(songs: &[Song]) -> Vec<i32> {
+        songs.iter().map(|song| song.id).collect()
+    }
+
+    #[test]
+    fn test_fifo_keeps_order() {
+        let songs = vec![song(1, "Anne"), song(2, "Anne"), song(3, "Bob")];
+        assert_eq!(ids(&QueueOrder::Fifo.apply(songs)), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let songs = vec![
+            song(1, "Anne"),
+            song(2, "anne "),
+            song(3, "Anne"),
+            song(4, "Bob"),
+            song(5, "Bob"),
+            song(6, "Chloé"),
+        ];
+        assert_eq!(ids(&QueueOrder::RoundRobin.apply(songs)), vec![1, 4, 6, 2, 5, 3]);
+    }
+
+    #[test]
+    fn test_round_robin_undoes_manual_moves() {
+        // B1 moved below A2 by its position still sings in the first round
+        let moved = vec![song(1, "Anne"), song(3, "Anne"), song(2, "Bob")];
+        assert_eq!(ids(&QueueOrder::RoundRobin.apply(moved)), vec![1, 2, 3]);
+        assert!(!QueueOrder::RoundRobin.allows_manual_moves());
+        assert!(QueueOrder::Fifo.allows_manual_moves());
+    }
+
+    #[test]
+    fn test_round_robin_counts_current_singer() {
+        let mut playing = song(1, "Anne");
+        playing.status = Some("playing".to_string());
+        let songs = vec![playing, song(2, "Anne"), song(3, "Bob")];
+
+        assert_eq!(ids(&QueueOrder::RoundRobin.apply(songs)), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_round_robin_without_singer() {
+        let mut songs = vec![song(1, "Anne"), song(2, ""), song(3, "Anne"), song(4, "")];
+        songs[3].singer = None;
+        assert_eq!(ids(&QueueOrder::RoundRobin.apply(songs)), vec![1, 2, 4, 3]);
+    }
+
+    #[test]
+    fn test_from_config() {
+        assert_eq!(QueueOrder::from_config("round_robin"), QueueOrder::RoundRobin);
+        assert_eq!(QueueOrder::from_config("fifo"), QueueOrder::Fifo);
+        assert_eq!(QueueOrder::from_config(""), QueueOrder::Fifo);
+    }
+}
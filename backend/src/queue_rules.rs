@@ -0,0 +1,154 @@
+use std::fmt;
+use actix_web::web;
+use sqlx::PgConnection;
+
+use crate::config::get_config_value;
+use crate::song::Song;
+use crate::state::AppState;
+
+
+/// Limits applied when a guest adds a song to the queue, read from the config table
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct QueueRules {
+    pub max_songs_per_singer: i64,   // 0 means no limit
+    pub reject_duplicate_songs: bool,
+    pub song_cooldown_minutes: i32,  // 0 means no cooldown, counted from the time the song was played
+}
+
+/// Reason why a song cannot be added, shown to the guest
+#[derive(Clone, PartialEq, Debug)]
+pub enum Rejection {
+    SingerLimit { max: i64 },
+    AlreadyQueued,
+    Cooldown { minutes: i32 },
+}
+
+#[derive(Debug)]
+pub enum AddSongError {
+    Rejected(Rejection),
+    UnknownSong, // Not in the catalog anymore
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for AddSongError {
+    fn from(error: sqlx::Error) -> Self {
+        AddSongError::Database(error)
+    }
+}
+
+impl fmt::Display for AddSongError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddSongError::Rejected(rejection) => write!(f, "{}", rejection.message()),
+            AddSongError::UnknownSong => write!(f, "Cette chanson n'est plus dans la liste, rechargez la page."),
+            AddSongError::Database(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+
+impl Rejection {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Rejection::SingerLimit { .. } => "singer_limit",
+            Rejection::AlreadyQueued => "already_queued",
+            Rejection::Cooldown { .. } => "cooldown",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Rejection::SingerLimit { max } => format!(
+                "Vous avez déjà {} chanson(s) en attente, attendez votre tour avant d'en choisir une autre.", max
+            ),
+            Rejection::AlreadyQueued => "Cette chanson est déjà dans la liste d'attente.".to_string(),
+            Rejection::Cooldown { minutes } => format!(
+                "Cette chanson a déjà été chantée il y a moins de {} minutes, choisissez-en une autre.", minutes
+            ),
+        }
+    }
+}
+
+
+impl QueueRules {
+    pub fn from_values(max_songs_per_singer: Option<String>, reject_duplicate_songs: Option<String>, song_cooldown_minutes: Option<String>) -> QueueRules {
+        QueueRules {
+            max_songs_per_singer: max_songs_per_singer.and_then(|value| value.trim().parse().ok()).unwrap_or(0).max(0),
+            reject_duplicate_songs: reject_duplicate_songs.as_deref() == Some("yes"),
+            song_cooldown_minutes: song_cooldown_minutes.and_then(|value| value.trim().parse().ok()).unwrap_or(0).max(0),
+        }
+    }
+
+    pub async fn load(state: web::Data<AppState>) -> Result<QueueRules, sqlx::Error> {
+        Ok(QueueRules::from_values(
+            get_config_value("max_songs_per_singer", state.clone()).await?,
+            get_config_value("reject_duplicate_songs", state.clone()).await?,
+            get_config_value("song_cooldown_minutes", state).await?,
+        ))
+    }
+
+    /// Check an entry about to be added, run inside the insertion transaction
+    pub async fn check(&self, song: &Song, conn: &mut PgConnection) -> Result<Option<Rejection>, sqlx::Error> {
+        let singer = song.singer.as_deref().map(str::trim).unwrap_or_default();
+
+        // Jukebox entries have no singer and are not limited
+        if self.max_songs_per_singer > 0 && !singer.is_empty() {
+            let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM current_playlist WHERE status = 'queued' AND LOWER(TRIM(singer)) = LOWER($1)")
+                .bind(singer)
+                .fetch_one(&mut *conn)
+                .await?;
+            if pending >= self.max_songs_per_singer {
+                return Ok(Some(Rejection::SingerLimit { max: self.max_songs_per_singer }));
+            }
+        }
+
+        if self.reject_duplicate_songs {
+            let is_queued: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM current_playlist WHERE status IN ('queued', 'playing') AND song_id = $1)")
+                .bind(song.song_id)
+                .fetch_one(&mut *conn)
+                .await?;
+            if is_queued {
+                return Ok(Some(Rejection::AlreadyQueued));
+            }
+        }
+
+        // Songs still waiting have not been played, only the duplicate rule applies to them
+        if self.song_cooldown_minutes > 0 {
+            let is_recent: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM current_playlist WHERE status <> 'removed' AND song_id = $1
+                                    AND started_at > LOCALTIMESTAMP - make_interval(mins => $2))")
+                .bind(song.song_id)
+                .bind(self.song_cooldown_minutes)
+                .fetch_one(&mut *conn)
+                .await?;
+            if is_recent {
+                return Ok(Some(Rejection::Cooldown { minutes: self.song_cooldown_minutes }));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_from_values() {
+        let rules = QueueRules::from_values(Some("2".to_string()), Some("yes".to_string()), Some(" 30 ".to_string()));
+        assert_eq!(rules, QueueRules { max_songs_per_singer: 2, reject_duplicate_songs: true, song_cooldown_minutes: 30 });
+
+        let rules = QueueRules::from_values(Some("abc".to_string()), Some("no".to_string()), Some("-5".to_string()));
+        assert_eq!(rules, QueueRules::default());
+
+        assert_eq!(QueueRules::from_values(None, None, None), QueueRules::default());
+    }
+
+    #[test]
+    fn test_rejection_message() {
+        assert_eq!(Rejection::SingerLimit { max: 2 }.code(), "singer_limit");
+        assert!(Rejection::SingerLimit { max: 2 }.message().contains("2 chanson(s)"));
+        assert!(Rejection::Cooldown { minutes: 45 }.message().contains("45 minutes"));
+    }
+}
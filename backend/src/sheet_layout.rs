@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use actix_web::{web, post, Responder, HttpResponse};
+use actix_web::middleware::from_fn;
+use serde_json::json;
+
+use crate::auth::require_admin;
+use crate::config::{get_config_value, Config};
+use crate::catalog_diff::{ImportedRows, SkipReason, SkippedRow};
+use crate::song::Song;
+use crate::song_metadata::{MetadataCells, SongMetadata};
+use crate::state::AppState;
+
+
+// Config entry holding the layouts of every sheet as a JSON object keyed by sheet id
+const LAYOUTS_CONFIG_NAME: &str = "google_sheet_layouts";
+
+
+/// Where the song fields are in a Google Sheet.
+/// A column is either a header name (when the sheet has a header row) or a column letter.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct SheetLayout {
+    pub name: String, // Source shown to the guests, the sheet id when empty
+    pub range: String,
+    pub tabs: Vec<String>, // The range is read in each of these tabs, or once as is when empty
+    pub has_header: bool,
+    pub title_column: String,
+    pub artist_column: String,
+    pub lyrics_url_column: String, // Empty when the sheet has no lyrics
+    // Optional details of the songs, empty when the sheet does not have them
+    pub genre_column: String,
+    pub language_column: String,
+    pub year_column: String,
+    pub duet_column: String,
+    pub difficulty_column: String,
+    pub duration_column: String,
+}
+
+impl Default for SheetLayout {
+    fn default() -> Self {
+        SheetLayout {
+            name: String::new(),
+            range: "A:D".to_string(),
+            tabs: vec![],
+            has_header: true,
+            title_column: "A".to_string(),
+            artist_column: "B".to_string(),
+            lyrics_url_column: "C".to_string(),
+            genre_column: String::new(),
+            language_column: String::new(),
+            year_column: String::new(),
+            duet_column: String::new(),
+            difficulty_column: String::new(),
+            duration_column: String::new(),
+        }
+    }
+}
+
+/// Index of each song field in a row
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ColumnIndexes {
+    pub title: Option<usize>,
+    pub artist: Option<usize>,
+    pub lyrics_url: Option<usize>,
+    pub genre: Option<usize>,
+    pub language: Option<usize>,
+    pub year: Option<usize>,
+    pub duet: Option<usize>,
+    pub difficulty: Option<usize>,
+    pub duration: Option<usize>,
+}
+
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SheetLayoutRequest {
+    pub sheet_id: String,
+    #[serde(default)]
+    pub layout: Option<SheetLayout>,
+}
+
+
+#[post("/get-sheet-layout", wrap = "from_fn(require_admin)")]
+async fn get_sheet_layout(request: web::Json<SheetLayoutRequest>, state: web::Data<AppState>) -> impl Responder {
+
+    match SheetLayout::for_sheet(&request.sheet_id, state).await {
+        Ok(layout) => HttpResponse::Ok().json(layout),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+#[post("/change-sheet-layout", wrap = "from_fn(require_admin)")]
+async fn change_sheet_layout(request: web::Json<SheetLayoutRequest>, state: web::Data<AppState>) -> impl Responder {
+
+    let request = request.into_inner();
+    let layout = match request.layout {
+        Some(layout) if layout.title_column.trim().is_empty() || layout.artist_column.trim().is_empty() => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "ko",
+                "content": "title and artist columns are required",
+            }))
+        }
+        Some(layout) => layout,
+        None => {
+            return HttpResponse::BadRequest().json(json!({
+                "status": "ko",
+                "content": "layout is missing",
+            }))
+        }
+    };
+
+    match layout.save_for_sheet(&request.sheet_id, state).await {
+        Ok(()) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": layout,
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+
+/// Sheet ids of the `google_sheet_id` config, several ones are separated by commas or spaces
+pub fn sheet_ids(value: &str) -> Vec<String> {
+    let mut ids: Vec<String> = vec![];
+    for id in value.split(|c: char| c == ',' || c == ';' || c.is_whitespace()).filter(|id| !id.is_empty()) {
+        if !ids.iter().any(|known| known == id) {
+            ids.push(id.to_string());
+        }
+    }
+    ids
+}
+
+async fn load_layouts(state: web::Data<AppState>) -> Result<HashMap<String, SheetLayout>, sqlx::Error> {
+    let value = get_config_value(LAYOUTS_CONFIG_NAME, state).await?;
+    Ok(value
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default())
+}
+
+impl SheetLayout {
+    /// Layout configured for a sheet, the historical A/B/C layout when none is set
+    pub async fn for_sheet(sheet_id: &str, state: web::Data<AppState>) -> Result<SheetLayout, sqlx::Error> {
+        let mut layouts = load_layouts(state).await?;
+        Ok(layouts.remove(sheet_id).unwrap_or_default())
+    }
+
+    pub async fn save_for_sheet(&self, sheet_id: &str, state: web::Data<AppState>) -> Result<(), sqlx::Error> {
+        let mut layouts = load_layouts(state.clone()).await?;
+        layouts.insert(sheet_id.to_string(), self.clone());
+
+        let config = Config {
+            id: 0,
+            name: LAYOUTS_CONFIG_NAME.to_string(),
+            value: serde_json::to_string(&layouts).unwrap_or_default(),
+        };
+        config.change_config_in_db(state).await?;
+        Ok(())
+    }
+
+    /// Source name and A1 range of every part of the sheet to read
+    pub fn sources(&self, sheet_id: &str) -> Vec<(String, String)> {
+        let name = if self.name.trim().is_empty() { sheet_id.trim() } else { self.name.trim() };
+        let tabs: Vec<&str> = self.tabs.iter().map(|tab| tab.trim()).filter(|tab| !tab.is_empty()).collect();
+        if tabs.is_empty() {
+            return vec![(name.to_string(), self.range.clone())];
+        }
+
+        // The tab written in the range, if any, is replaced by each configured tab
+        let cells = self.range.rsplit('!').next().unwrap_or_default();
+        tabs.iter()
+            .map(|tab| (format!("{} / {}", name, tab), format!("'{}'!{}", tab.replace('\'', "''"), cells)))
+            .collect()
+    }
+
+    /// Resolve the configured columns against the header row of the sheet
+    pub fn column_indexes(&self, header: Option<&[String]>) -> ColumnIndexes {
+        let header = if self.has_header { header } else { None };
+        ColumnIndexes {
+            title: column_index(&self.title_column, header),
+            artist: column_index(&self.artist_column, header),
+            lyrics_url: column_index(&self.lyrics_url_column, header),
+            genre: column_index(&self.genre_column, header),
+            language: column_index(&self.language_column, header),
+            year: column_index(&self.year_column, header),
+            duet: column_index(&self.duet_column, header),
+            difficulty: column_index(&self.difficulty_column, header),
+            duration: column_index(&self.duration_column, header),
+        }
+    }
+
+    /// Map the rows of a sheet (or of an imported CSV file) to songs, `first_row` is the number of the first row in the sheet
+    pub fn read_rows(&self, rows: &[Vec<String>], first_row: usize) -> ImportedRows {
+        let header = if self.has_header { rows.first() } else { None };
+        let columns = self.column_indexes(header.map(|names| names.as_slice()));
+        let cell = |row: &Vec<String>, index: Option<usize>| {
+            index.and_then(|index| row.get(index)).filter(|value| !value.trim().is_empty()).cloned()
+        };
+
+        let mut imported = ImportedRows::default();
+        let data_rows = rows
+            .iter()
+            .enumerate()
+            .skip(if self.has_header { 1 } else { 0 })//skipping the column names
+            .filter(|(_, row)| row.iter().any(|value| !value.trim().is_empty()));// blank rows are not mistakes
+
+        for (index, row) in data_rows {
+            // Attempt to map each row to a Song, the lyrics are optional
+            if let (Some(artist), Some(title)) = (cell(row, columns.artist), cell(row, columns.title)) {
+                let song = Song {
+                    id: (imported.songs.len() + 1) as i32,
+                    artist,
+                    title,
+                    lyrics_url: cell(row, columns.lyrics_url).unwrap_or_default(),
+                    singer: None,
+                    status: None,
+                    song_id: None,
+                    source: None,
+                    metadata: SongMetadata::from_cells(MetadataCells {
+                        genre: cell(row, columns.genre),
+                        language: cell(row, columns.language),
+                        year: cell(row, columns.year),
+                        duet: cell(row, columns.duet),
+                        difficulty: cell(row, columns.difficulty),
+                        duration: cell(row, columns.duration),
+                    }),
+                };
+                imported.songs.push((first_row + index, song));
+            } else {
+                imported.skipped.push(SkippedRow { row: first_row + index, reason: SkipReason::MissingColumns, source: None });
+            }
+        }
+        imported
+    }
+}
+
+
+// A header name wins over a column letter, so a column named "A" is still found
+fn column_index(column: &str, header: Option<&[String]>) -> Option<usize> {
+    let column = column.trim();
+    if column.is_empty() {
+        return None;
+    }
+
+    let header_index = header.and_then(|names| {
+        names.iter().position(|name| name.trim().to_lowercase() == column.to_lowercase())
+    });
+
+    header_index.or_else(|| column_letter_index(column))
+}
+
+// "A" -> 0, "Z" -> 25, "AA" -> 26
+fn column_letter_index(column: &str) -> Option<usize> {
+    if column.is_empty() || !column.chars().all(|c| c.is_ascii_alphabetic()) || column.len() > 3 {
+        return None;
+    }
+    column
+        .to_ascii_uppercase()
+        .chars()
+        .try_fold(0usize, |index, c| Some(index * 26 + (c as usize - 'A' as usize + 1)))
+        .map(|index| index - 1)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_letter_index() {
+        assert_eq!(column_letter_index("A"), Some(0));
+        assert_eq!(column_letter_index("c"), Some(2));
+        assert_eq!(column_letter_index("AA"), Some(26));
+        assert_eq!(column_letter_index("Titre"), None);
+        assert_eq!(column_letter_index("1"), None);
+    }
+
+    #[test]
+    fn test_column_indexes_from_header() {
+        let header = vec!["Artiste".to_string(), " Titre ".to_string(), "Langue".to_string(), "Paroles".to_string()];
+        let layout = SheetLayout {
+            range: "A:E".to_string(),
+            has_header: true,
+            title_column: "titre".to_string(),
+            artist_column: "Artiste".to_string(),
+            lyrics_url_column: "Paroles".to_string(),
+            language_column: "langue".to_string(),
+            year_column: "Année".to_string(), // missing from the header, and not a column letter
+            ..SheetLayout::default()
+        };
+
+        assert_eq!(layout.column_indexes(Some(&header)), ColumnIndexes {
+            title: Some(1),
+            artist: Some(0),
+            lyrics_url: Some(3),
+            language: Some(2),
+            ..ColumnIndexes::default()
+        });
+    }
+
+    #[test]
+    fn test_sheet_ids() {
+        assert_eq!(sheet_ids(" 1KWhp9 "), vec!["1KWhp9"]);
+        assert_eq!(sheet_ids("1KWhp9, 1OReTp\n1KWhp9"), vec!["1KWhp9", "1OReTp"]);
+        assert!(sheet_ids("").is_empty());
+    }
+
+    #[test]
+    fn test_sources() {
+        let layout = SheetLayout { range: "'Feuille 1'!A2:D".to_string(), ..SheetLayout::default() };
+        assert_eq!(layout.sources("1KWhp9"), vec![("1KWhp9".to_string(), "'Feuille 1'!A2:D".to_string())]);
+
+        let layout = SheetLayout {
+            name: "Carpe".to_string(),
+            tabs: vec!["Rock".to_string(), " ".to_string(), "Chanson d'amour".to_string()],
+            ..layout
+        };
+        assert_eq!(layout.sources("1KWhp9"), vec![
+            ("Carpe / Rock".to_string(), "'Rock'!A2:D".to_string()),
+            ("Carpe / Chanson d'amour".to_string(), "'Chanson d''amour'!A2:D".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_column_indexes_without_header() {
+        let header = vec!["B".to_string(), "A".to_string()];
+        let layout = SheetLayout {
+            has_header: false,
+            lyrics_url_column: "".to_string(),
+            ..SheetLayout::default()
+        };
+
+        assert_eq!(layout.column_indexes(Some(&header)), ColumnIndexes {
+            title: Some(0),
+            artist: Some(1),
+            lyrics_url: None,
+            ..ColumnIndexes::default()
+        });
+    }
+}
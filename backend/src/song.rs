@@ -1,12 +1,19 @@
 use serde::{Deserialize, Serialize};
 use actix_web::{web, post, get, Responder, HttpResponse};
+use actix_web::middleware::from_fn;
 use sqlx::FromRow;
 use serde_json::json;
-use log::debug;
 
-use crate::config::Config;
 use crate::state::AppState; 
-use crate::google_sheet_response; 
+use crate::auth::require_admin;
+use crate::catalog;
+use crate::catalog_page::CatalogQuery;
+use crate::song_metadata::SongMetadata;
+use crate::catalog_refresh;
+use crate::events::LiveEvent;
+use crate::queue_order::QueueOrder;
+use crate::queue_rules::{AddSongError, QueueRules};
+use crate::moderation::{bad_request, rate_limit, Moderation, MAX_SINGER_LENGTH};
 
 
 
@@ -17,22 +24,109 @@ pub struct Song {
     pub title: String,
     pub lyrics_url: String,
     pub singer: Option<String>,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub status: Option<String>, // Only set on queue entries, see SongStatus
+    #[serde(default)]
+    #[sqlx(default)]
+    pub song_id: Option<i32>, // Catalog song of a queue entry, `id` being the entry itself
+    #[serde(default)]
+    #[sqlx(default)]
+    pub source: Option<String>, // Sheet or tab the catalog song was imported from
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub metadata: SongMetadata,
+}
+
+
+/// Lifecycle of a queue entry
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SongStatus {
+    Queued,
+    Playing,
+    Sung,
+    Skipped,
+    Removed,
+}
+
+impl SongStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SongStatus::Queued => "queued",
+            SongStatus::Playing => "playing",
+            SongStatus::Sung => "sung",
+            SongStatus::Skipped => "skipped",
+            SongStatus::Removed => "removed",
+        }
+    }
 }
 
 impl Song {
-    pub async fn insert_song_into_playlist(&self, state: web::Data<AppState>) -> Result<Song, sqlx::Error>{
-        sqlx::query_as("INSERT INTO current_playlist(artist, title, lyrics_url, singer) VALUES ($1, $2, $3, $4) RETURNING id, artist, title, lyrics_url, singer")
-            .bind(&self.artist)
-            .bind(&self.title)
-            .bind(&self.lyrics_url)
-            .bind(&self.singer)
-            .fetch_one(&state.pool).await
+    pub fn is_playing(&self) -> bool {
+        self.status.as_deref() == Some(SongStatus::Playing.as_str())
+    }
+
+    /// Catalog song of the tests, the other fields are set with `..Song::for_test(..)`
+    #[cfg(test)]
+    pub fn for_test(id: i32, artist: &str, title: &str) -> Song {
+        Song {
+            id,
+            artist: artist.to_string(),
+            title: title.to_string(),
+            lyrics_url: String::new(),
+            singer: None,
+            status: None,
+            song_id: None,
+            source: None,
+            metadata: SongMetadata::default(),
+        }
+    }
+
+    pub async fn insert_song_into_playlist(&self, state: web::Data<AppState>) -> Result<Song, AddSongError>{
+        let rules = QueueRules::load(state.clone()).await?;
+        let mut tx = state.pool.begin().await?;
+
+        // One addition at a time, so a double tap cannot get past the rules twice
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('current_playlist'))")
+            .execute(&mut *tx)
+            .await?;
+
+        // The guest picked a song of the catalog, `id` is its catalog id
+        let catalog_song: Option<Song> = sqlx::query_as("SELECT id, artist, title, lyrics_url, NULL::text AS singer FROM songs WHERE id = $1 AND active")
+            .bind(self.id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let Some(catalog_song) = catalog_song else {
+            return Err(AddSongError::UnknownSong);
+        };
+        let entry = Song {
+            singer: self.singer.clone(),
+            song_id: Some(catalog_song.id),
+            ..catalog_song
+        };
+
+        if let Some(rejection) = rules.check(&entry, &mut tx).await? {
+            return Err(AddSongError::Rejected(rejection));
+        }
+
+        let (id, status): (i32, String) = sqlx::query_as("INSERT INTO current_playlist(song_id, singer, position) 
+                            VALUES ($1, $2, (SELECT COALESCE(MAX(position), 0) + 1 FROM current_playlist WHERE status = 'queued')) 
+                            RETURNING id, status")
+            .bind(entry.song_id)
+            .bind(&entry.singer)
+            .fetch_one(&mut *tx).await?;
+
+        tx.commit().await?;
+        state.notify(LiveEvent::Playlist);
+        Ok(Song { id, status: Some(status), ..entry })
     }
 
     pub async fn delete_song_from_playlist(&self, state: web::Data<AppState>) -> Result<bool, sqlx::Error>
     {
-        let result = sqlx::query("UPDATE current_playlist SET is_deleted = TRUE WHERE id = $1")
-            .bind(&self.id)
+        let result = sqlx::query("UPDATE current_playlist SET status = $1, ended_at = CURRENT_TIMESTAMP WHERE id = $2 AND status IN ('queued', 'playing')")
+            .bind(SongStatus::Removed.as_str())
+            .bind(self.id)
             .execute(&state.pool)
             .await; 
 
@@ -40,6 +134,9 @@ impl Song {
             Ok(query_result) => {
                 // Check if any rows were affected
                 let rows_affected = query_result.rows_affected();
+                if rows_affected > 0 {
+                    state.notify(LiveEvent::Playlist);
+                }
                 Ok(rows_affected > 0) // Returns true if at least one row was deleted
             }
             Err(e) => Err(e), // Propagate the error
@@ -49,60 +146,167 @@ impl Song {
 }
 
 
+// Queue entries with their catalog song
+const PLAYLIST_QUERY: &str = "SELECT p.id, s.artist, s.title, s.lyrics_url, p.singer, p.status, p.song_id
+                              FROM current_playlist p JOIN songs s ON s.id = p.song_id
+                              WHERE p.status IN ('playing', 'queued') 
+                              ORDER BY p.status = 'playing' DESC, p.position ASC, p.created_at ASC";
+
+// The song being performed comes first, then the queue in the configured order
 pub async fn fetch_song_playlist(state: web::Data<AppState>) -> Result<Vec<Song>, sqlx::Error> {
-    sqlx::query_as("select * FROM current_playlist WHERE is_deleted = FALSE ORDER BY created_at ASC")
-    .fetch_all(&state.pool)
-    .await
+    let queue_order = QueueOrder::current(state.clone()).await?;
+    let songs = sqlx::query_as(PLAYLIST_QUERY)
+        .fetch_all(&state.pool)
+        .await?;
+    Ok(queue_order.apply(songs))
+}
+
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct NextSong {
+    #[serde(default)]
+    pub skipped: bool, // The current song was not performed
+}
+
+impl NextSong {
+    /// End the song being performed and start the first queued one, returns the new current song
+    pub async fn advance_playlist(&self, state: web::Data<AppState>) -> Result<Option<Song>, sqlx::Error> {
+        let queue_order = QueueOrder::current(state.clone()).await?;
+        let mut tx = state.pool.begin().await?;
+
+        // Lock the playlist so two maestros cannot start two songs at once
+        let playlist: Vec<Song> = sqlx::query_as(&format!("{} FOR UPDATE OF p", PLAYLIST_QUERY))
+            .fetch_all(&mut *tx)
+            .await?;
+        let next_id = queue_order.apply(playlist).into_iter().find(|song| !song.is_playing()).map(|song| song.id);
+
+        let outcome = if self.skipped { SongStatus::Skipped } else { SongStatus::Sung };
+        sqlx::query("UPDATE current_playlist SET status = $1, ended_at = CURRENT_TIMESTAMP WHERE status = 'playing'")
+            .bind(outcome.as_str())
+            .execute(&mut *tx)
+            .await?;
+
+        let new_current: Option<Song> = sqlx::query_as("UPDATE current_playlist p SET status = 'playing', started_at = CURRENT_TIMESTAMP 
+                            FROM songs s WHERE p.id = $1 AND s.id = p.song_id
+                            RETURNING p.id, s.artist, s.title, s.lyrics_url, p.singer, p.status, p.song_id")
+            .bind(next_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        state.notify(LiveEvent::Playlist);
+        Ok(new_current)
+    }
+}
+
+
+#[derive(Debug)]
+pub enum MoveSongError {
+    RoundRobin, // The order comes from the singers' turns, a manual position would be undone
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for MoveSongError {
+    fn from(error: sqlx::Error) -> Self {
+        MoveSongError::Database(error)
+    }
+}
+
+impl std::fmt::Display for MoveSongError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveSongError::RoundRobin => write!(f, "L'ordre « Chacun son tour » décide de l'ordre de passage, les chansons ne peuvent pas être déplacées."),
+            MoveSongError::Database(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct MoveSong {
+    pub id: i32,
+    pub index: usize, // New index among the queued songs as displayed, 0 is the top
+}
+
+impl MoveSong {
+    /// Move a queue entry and renumber the whole queue in one transaction.
+    /// The positions are rewritten following the displayed order so the index matches what the maestro sees.
+    pub async fn move_song_in_playlist(&self, state: web::Data<AppState>) -> Result<bool, MoveSongError> {
+        let queue_order = QueueOrder::current(state.clone()).await?;
+        self.move_with_order(queue_order, state).await
+    }
+
+    /// Refused in round robin, the rounds would put the song back in its place
+    async fn move_with_order(&self, queue_order: QueueOrder, state: web::Data<AppState>) -> Result<bool, MoveSongError> {
+        if !queue_order.allows_manual_moves() {
+            return Err(MoveSongError::RoundRobin);
+        }
+        let mut tx = state.pool.begin().await?;
+
+        // Lock the queue rows so concurrent moves are applied one after the other
+        let queue: Vec<Song> = sqlx::query_as("SELECT p.id, s.artist, s.title, s.lyrics_url, p.singer, p.status, p.song_id
+                                               FROM current_playlist p JOIN songs s ON s.id = p.song_id
+                                               WHERE p.status = 'queued' ORDER BY p.position ASC, p.created_at ASC FOR UPDATE OF p")
+            .fetch_all(&mut *tx)
+            .await?;
+        let mut ids: Vec<i32> = queue_order.apply(queue).iter().map(|song| song.id).collect();
+
+        if !move_id_to_index(&mut ids, self.id, self.index) {
+            return Ok(false);
+        }
+
+        let positions: Vec<i32> = (1..=ids.len() as i32).collect();
+        sqlx::query("UPDATE current_playlist SET position = ordered.position 
+                    FROM UNNEST($1::int[], $2::int[]) AS ordered(id, position) 
+                    WHERE current_playlist.id = ordered.id")
+            .bind(&ids)
+            .bind(&positions)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        state.notify(LiveEvent::Playlist);
+        Ok(true)
+    }
+}
+
+/// Move `id` to `index` (clamped to the end of the list), returns false if `id` is not in the list
+pub fn move_id_to_index(ids: &mut Vec<i32>, id: i32, index: usize) -> bool {
+    match ids.iter().position(|&current| current == id) {
+        Some(current_index) => {
+            let moved = ids.remove(current_index);
+            ids.insert(index.min(ids.len()), moved);
+            true
+        }
+        None => false,
+    }
 }
 
 
-#[get("/song-update")]
+#[get("/song-update", wrap = "from_fn(require_admin)")]
 async fn song_update(data: web::Data<AppState>) -> impl Responder {
 
-    let google_sheet_id= Config {
-            id:0, 
-            name: "google_sheet_id".to_string(), 
-            value : "".to_string()
-        };
-    let google_sheet_id = google_sheet_id.get_config_from_name(data.clone()).await.unwrap();
-
-    match google_sheet_response::fetch_google_sheet(google_sheet_id.value).await {
-        Ok(content) => {
-            let song_list = content.transform_google_format_to_song();
-            data.update_playlist_cache(song_list);
-            HttpResponse::Ok().body("Ok :p")
-        },
-        Err(err) => HttpResponse::InternalServerError().body(format!("Error fetching document: {}", err)),
+    match catalog_refresh::refresh_catalog(data).await {
+        Ok(diff) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": diff,
+        })),
+        Err(err) => HttpResponse::build(err.status_code()).json(json!({
+            "status": "ko",
+            "content": err.to_string(),
+        })),
     }
 }
 
-//get the songs from Google or cache is exists
+//get a page of the songs from the cache, the database or the catalog source
 #[get("/song-data")]
-async fn song_data(data: web::Data<AppState>) -> impl Responder {
+async fn song_data(query: web::Query<CatalogQuery>, data: web::Data<AppState>) -> impl Responder {
 
-    let song_list;
-    let google_sheet_id= Config {
-            id:0, 
-            name: "google_sheet_id".to_string(), 
-            value : "".to_string()
-        };
-    let google_sheet_id = google_sheet_id.get_config_from_name(data.clone()).await.unwrap();
-
-    // if the song collection does'nt exists we download it ortherwise we use the cache one
-    if data.is_playlist_cache_empty() {
-        debug!("Song list not cache creating it.");
-        let content = google_sheet_response::fetch_google_sheet(google_sheet_id.value).await.expect("Error fetching document");
-            debug!("{:?}", content);
-            //println!("{:?}", content);
-            song_list = content.transform_google_format_to_song();
-            data.update_playlist_cache(song_list.clone());
-            HttpResponse::Ok().json(song_list)
-    } else {
-        debug!("Song list existing. Loading it");
-        match data.read_from_cache() {
-            Some(songs) => HttpResponse::Ok().json(songs),
-            None => HttpResponse::InternalServerError().body("Error reading from cache to load the songs list"),
-        }
+    match catalog::catalog_songs(data).await {
+        Ok(songs) => HttpResponse::Ok().json(query.apply(&songs)),
+        Err(err) => HttpResponse::build(err.status_code()).json(json!({
+            "status": "ko",
+            "content": err.to_string(),
+        })),
     }
 }
 
@@ -117,9 +321,20 @@ async fn song_playlist(state: web::Data<AppState>) -> impl Responder {
     }
 }
 
-#[post("/add-song")]
+#[post("/add-song", wrap = "from_fn(rate_limit)")]
 async fn add_song(song: web::Json<Song>, state: web::Data<AppState>) -> impl Responder {
 
+    let checked = match Moderation::load(state.clone()).await {
+        Ok(moderation) => moderation.check("Le nom", song.singer.as_deref().unwrap_or_default(), MAX_SINGER_LENGTH, false),
+        Err(error) => return HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    };
+    if let Err(invalid) = checked {
+        return bad_request(&invalid);
+    }
+
     let song = song.into_inner().insert_song_into_playlist(state).await   ;
     
     match song {
@@ -127,6 +342,16 @@ async fn add_song(song: web::Json<Song>, state: web::Data<AppState>) -> impl Res
             "status": "ok",
             "content": content,
         })),
+        Err(AddSongError::Rejected(rejection)) => HttpResponse::Conflict().json(json!({
+            "status": "ko",
+            "code": rejection.code(),
+            "content": rejection.message(),
+        })),
+        Err(AddSongError::UnknownSong) => HttpResponse::NotFound().json(json!({
+            "status": "ko",
+            "code": "unknown_song",
+            "content": AddSongError::UnknownSong.to_string(),
+        })),
         Err(error) => HttpResponse::InternalServerError().json(json!({
             "status": "ko",
             "content": error.to_string(),
@@ -136,7 +361,7 @@ async fn add_song(song: web::Json<Song>, state: web::Data<AppState>) -> impl Res
 }
  
 
-#[post("/delete-song")]
+#[post("/delete-song", wrap = "from_fn(require_admin)")]
 async fn delete_song(song: web::Json<Song>, state: web::Data<AppState>) -> impl Responder {
     let deleted = song.into_inner().delete_song_from_playlist(state).await;
 
@@ -156,4 +381,95 @@ async fn delete_song(song: web::Json<Song>, state: web::Data<AppState>) -> impl
             "content": error.to_string(),
         })),
     }
-}
\ No newline at end of file
+}
+
+
+#[post("/move-song", wrap = "from_fn(require_admin)")]
+async fn move_song(move_request: web::Json<MoveSong>, state: web::Data<AppState>) -> impl Responder {
+    let moved = move_request.into_inner().move_song_in_playlist(state).await;
+
+    match moved {
+        Ok(true) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": "one song moved",
+        })),
+        Ok(false) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": "no song moved",
+        })),
+        Err(MoveSongError::RoundRobin) => HttpResponse::Conflict().json(json!({
+            "status": "ko",
+            "code": "round_robin",
+            "content": MoveSongError::RoundRobin.to_string(),
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+
+#[post("/next-song", wrap = "from_fn(require_admin)")]
+async fn next_song(next: web::Json<NextSong>, state: web::Data<AppState>) -> impl Responder {
+    let next_song = next.into_inner().advance_playlist(state).await;
+
+    match next_song {
+        Ok(song) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": song,
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+    use std::fs;
+    use std::path::Path;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    #[allow(non_snake_case)]
+    struct Secrets {
+        pub DATABASE_URL: String,
+    }
+
+    fn load_secrets() -> Secrets {
+        let content = fs::read_to_string(Path::new("Secrets.toml"))
+            .expect("Failed to read Secrets.toml");
+        toml::from_str(&content)
+            .expect("Failed to parse Secrets.toml")
+    }
+
+    #[actix_web::test]
+    async fn test_move_refused_in_round_robin() {
+        // Refused before any query, the lazy pool never connects
+        let state = web::Data::new(AppState::new(PgPool::connect_lazy(&load_secrets().DATABASE_URL).unwrap()));
+
+        let moved = MoveSong { id: 1, index: 2 }.move_with_order(QueueOrder::RoundRobin, state).await;
+        assert!(matches!(moved, Err(MoveSongError::RoundRobin)));
+    }
+
+    #[test]
+    fn test_move_id_to_index() {
+        let mut ids = vec![1, 2, 3, 4];
+        assert!(move_id_to_index(&mut ids, 3, 0));
+        assert_eq!(ids, vec![3, 1, 2, 4]);
+
+        assert!(move_id_to_index(&mut ids, 3, 2));
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+
+        assert!(move_id_to_index(&mut ids, 1, 99));
+        assert_eq!(ids, vec![2, 3, 4, 1]);
+
+        assert!(!move_id_to_index(&mut ids, 5, 0));
+        assert_eq!(ids, vec![2, 3, 4, 1]);
+    }
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+
+/// Optional details of a catalog song, read from extra columns of the sheet or file
+#[derive(Clone, PartialEq, Serialize, Deserialize, FromRow, Debug, Default)]
+#[serde(default)]
+pub struct SongMetadata {
+    #[sqlx(default)]
+    pub genre: Option<String>,
+    #[sqlx(default)]
+    pub language: Option<String>,
+    #[sqlx(default)]
+    pub year: Option<i32>,
+    #[sqlx(default)]
+    pub duet: bool,
+    #[sqlx(default)]
+    pub difficulty: Option<String>,
+    #[sqlx(default)]
+    pub duration: Option<i32>, // In seconds
+}
+
+/// Raw text of the metadata cells of a row, empty cells are None
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MetadataCells {
+    pub genre: Option<String>,
+    pub language: Option<String>,
+    pub year: Option<String>,
+    pub duet: Option<String>,
+    pub difficulty: Option<String>,
+    pub duration: Option<String>,
+}
+
+
+impl SongMetadata {
+    /// Cells that can't be understood are ignored, the song is still imported
+    pub fn from_cells(cells: MetadataCells) -> SongMetadata {
+        SongMetadata {
+            genre: text(cells.genre),
+            language: text(cells.language),
+            year: cells.year.as_deref().and_then(parse_year),
+            duet: cells.duet.as_deref().map(parse_duet).unwrap_or(false),
+            difficulty: text(cells.difficulty),
+            duration: cells.duration.as_deref().and_then(parse_duration),
+        }
+    }
+
+    /// First year of the decade, 1987 -> 1980
+    pub fn decade(&self) -> Option<i32> {
+        self.year.map(|year| year - year.rem_euclid(10))
+    }
+}
+
+
+fn text(cell: Option<String>) -> Option<String> {
+    cell.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+// The first 4 digit number of the cell, "1984" or "12/05/1984"
+fn parse_year(cell: &str) -> Option<i32> {
+    cell.split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() == 4)
+        .and_then(|year| year.parse().ok())
+}
+
+// A mark in a "Duo" column, in french or english
+fn parse_duet(cell: &str) -> bool {
+    matches!(
+        cell.trim().to_lowercase().as_str(),
+        "x" | "1" | "oui" | "o" | "yes" | "y" | "true" | "vrai" | "duo" | "duet"
+    )
+}
+
+// "3:45" or "1:02:03", or a number of seconds
+fn parse_duration(cell: &str) -> Option<i32> {
+    cell.trim()
+        .split(':')
+        .map(|part| part.trim().parse::<i32>().ok())
+        .try_fold(0i32, |seconds, part| seconds.checked_mul(60)?.checked_add(part?))
+        .filter(|seconds| *seconds > 0)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_from_cells() {
+        let metadata = SongMetadata::from_cells(MetadataCells {
+            genre: Some(" Variété ".to_string()),
+            language: Some("".to_string()),
+            year: Some("12/05/1987".to_string()),
+            duet: Some("Oui".to_string()),
+            difficulty: None,
+            duration: Some("3:45".to_string()),
+        });
+
+        assert_eq!(metadata, SongMetadata {
+            genre: Some("Variété".to_string()),
+            language: None,
+            year: Some(1987),
+            duet: true,
+            difficulty: None,
+            duration: Some(225),
+        });
+        assert_eq!(metadata.decade(), Some(1980));
+    }
+
+    #[test]
+    fn test_parse_cells() {
+        assert_eq!(parse_year("années 80"), None);
+        assert_eq!(parse_year("2003"), Some(2003));
+        assert_eq!(parse_duration("1:02:03"), Some(3723));
+        assert_eq!(parse_duration("245"), Some(245));
+        assert_eq!(parse_duration("3 min"), None);
+        assert_eq!(parse_duration("99999999:0:0"), None);
+        assert!(parse_duet("x"));
+        assert!(!parse_duet("non"));
+    }
+}
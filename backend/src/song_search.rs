@@ -0,0 +1,207 @@
+use std::cmp::Ordering;
+use actix_web::{web, get, Responder, HttpResponse};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::catalog::{self, normalize};
+use crate::song::Song;
+use crate::state::AppState;
+
+
+// Number of songs answered when the request does not say
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 500;
+
+// Score of a query word depending on how it matches a word of the song
+const EXACT_SCORE: f32 = 3.0;
+const PREFIX_SCORE: f32 = 2.0;
+const CONTAINS_SCORE: f32 = 1.5;
+const FUZZY_SCORE: f32 = 1.0;
+// Added when the whole query is the artist or the title
+const FULL_MATCH_BONUS: f32 = 2.0;
+
+
+#[derive(Deserialize, Debug)]
+struct SearchQuery {
+    #[serde(default)]
+    q: String,
+    limit: Option<usize>,
+}
+
+
+#[get("/songs/search")]
+async fn search_songs(query: web::Query<SearchQuery>, state: web::Data<AppState>) -> impl Responder {
+
+    let songs = match catalog::catalog_songs(state).await {
+        Ok(songs) => songs,
+        Err(err) => return HttpResponse::build(err.status_code()).json(json!({
+            "status": "ko",
+            "content": err.to_string(),
+        })),
+    };
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    HttpResponse::Ok().json(search(&songs, &query.q, limit))
+}
+
+
+/// Songs matching every word of the query, best matches first.
+/// Accents and case are ignored and a word may have a typo or two, depending on its length.
+pub fn search(songs: &[Song], query: &str, limit: usize) -> Vec<Song> {
+    let query = normalize(query);
+    let tokens: Vec<&str> = query.split(' ').filter(|token| !token.is_empty()).collect();
+    if tokens.is_empty() {
+        return vec![];
+    }
+
+    let mut matches: Vec<(f32, &Song)> = songs
+        .iter()
+        .filter_map(|song| song_score(song, &query, &tokens).map(|score| (score, song)))
+        .collect();
+
+    matches.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .partial_cmp(score_a)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.artist.to_lowercase().cmp(&b.artist.to_lowercase()))
+            .then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase()))
+    });
+
+    matches.into_iter().take(limit).map(|(_, song)| song.clone()).collect()
+}
+
+// None when a word of the query matches nothing in the song
+fn song_score(song: &Song, query: &str, tokens: &[&str]) -> Option<f32> {
+    let artist = normalize(&song.artist);
+    let title = normalize(&song.title);
+    let words: Vec<&str> = artist.split(' ').chain(title.split(' ')).filter(|word| !word.is_empty()).collect();
+
+    let mut score = 0.0;
+    for token in tokens {
+        let best = words
+            .iter()
+            .map(|word| token_score(token, word))
+            .fold(0.0, f32::max);
+        if best == 0.0 {
+            return None;
+        }
+        score += best;
+    }
+
+    if artist == query || title == query {
+        score += FULL_MATCH_BONUS;
+    }
+    Some(score)
+}
+
+fn token_score(token: &str, word: &str) -> f32 {
+    if word == token {
+        return EXACT_SCORE;
+    }
+    if word.starts_with(token) {
+        return PREFIX_SCORE;
+    }
+    if token.len() >= 3 && word.contains(token) {
+        return CONTAINS_SCORE;
+    }
+
+    // While typing, the query word is compared with the beginning of the song word too
+    let allowed = allowed_typos(token);
+    if allowed == 0 {
+        return 0.0;
+    }
+    let prefix: String = word.chars().take(token.chars().count()).collect();
+    let distance = edit_distance(token, word).min(edit_distance(token, &prefix));
+    if distance <= allowed {
+        FUZZY_SCORE - 0.25 * distance as f32
+    } else {
+        0.0
+    }
+}
+
+// Short words must be typed right, "abba" should not find "abbe"
+fn allowed_typos(token: &str) -> usize {
+    match token.chars().count() {
+        0..=4 => 0,
+        5..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Damerau-Levenshtein distance (optimal string alignment), swapping two letters is one typo
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Vec<Song> {
+        vec![
+            Song::for_test(1, "Céline Dion", "Pour que tu m'aimes encore"),
+            Song::for_test(2, "Queen", "Bohemian Rhapsody"),
+            Song::for_test(3, "Charles Aznavour", "La Bohème"),
+            Song::for_test(4, "Dion", "Runaround Sue"),
+            Song::for_test(5, "ABBA", "Dancing Queen"),
+        ]
+    }
+
+    fn ids(songs: Vec<Song>) -> Vec<i32> {
+        songs.iter().map(|song| song.id).collect()
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("bohemain", "bohemian"), 1);
+        assert_eq!(edit_distance("celin", "celine"), 1);
+        assert_eq!(edit_distance("queen", "queen"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_search_ignores_accents_and_case() {
+        assert_eq!(ids(search(&catalog(), "celine dion", 10)), vec![1]);
+        assert_eq!(ids(search(&catalog(), "BOHÈME", 10)), vec![3, 2]);
+    }
+
+    #[test]
+    fn test_search_tolerates_typos() {
+        assert_eq!(ids(search(&catalog(), "bohemain", 10)), vec![2]);
+        assert_eq!(ids(search(&catalog(), "celien", 10)), vec![1]);
+        assert!(search(&catalog(), "abbe", 10).is_empty());
+    }
+
+    #[test]
+    fn test_search_ranks_best_matches_first() {
+        // "Queen" the artist before "Dancing Queen" the title
+        assert_eq!(ids(search(&catalog(), "queen", 10)), vec![2, 5]);
+        // Every word must match, in any order
+        assert_eq!(ids(search(&catalog(), "rhapsody queen", 10)), vec![2]);
+        assert_eq!(ids(search(&catalog(), "dion", 1)), vec![4]);
+        assert!(search(&catalog(), "  ", 10).is_empty());
+    }
+}
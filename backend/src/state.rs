@@ -1,14 +1,56 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use crate::song::Song; 
+use crate::events::LiveEvent;
+use crate::catalog_refresh::RefreshStatus;
+use crate::moderation::{RateLimiter, TrustedProxies};
+use crate::google_sheet_response::GoogleClient;
+use crate::auth;
 use sqlx::PgPool;
+use tokio::sync::broadcast;
+
+// Number of events a slow client can fall behind before being resynced
+const EVENTS_CAPACITY: usize = 64;
 
 #[derive(Clone)]
 pub struct AppState {
     pub playlist_cache: Arc<RwLock<Vec<Song>>>,
     pub pool: PgPool,
+    pub events: broadcast::Sender<LiveEvent>,
+    pub catalog_refresh: Arc<RwLock<RefreshStatus>>,
+    pub google: GoogleClient,
+    pub rate_limiter: Arc<Mutex<RateLimiter>>,
+    pub trusted_proxies: TrustedProxies,
+    pub jwt_secret: Option<String>, // Signs the admin sessions, from JWT_SECRET
 }
 
 impl AppState {
+    pub fn new(pool: PgPool) -> AppState {
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        AppState {
+            playlist_cache: Arc::new(RwLock::new(vec![])),
+            pool,
+            events,
+            catalog_refresh: Arc::new(RwLock::new(RefreshStatus::default())),
+            google: GoogleClient::from_env(),
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::default())),
+            trusted_proxies: TrustedProxies::from_env(),
+            jwt_secret: auth::jwt_secret(),
+        }
+    }
+
+    /// Push an event to the connected clients, nobody listening is not an error
+    pub fn notify(&self, event: LiveEvent) {
+        let _ = self.events.send(event);
+    }
+
+    pub fn refresh_status(&self) -> RefreshStatus {
+        self.catalog_refresh.read().unwrap().clone()
+    }
+
+    pub fn update_refresh_status(&self, update: impl FnOnce(&mut RefreshStatus)) {
+        update(&mut self.catalog_refresh.write().unwrap());
+    }
+
     pub fn update_playlist_cache(&self, new_songs: Vec<Song>) {
         // Obtain a mutable lock on the playlist_cache
         let mut playlist = self.playlist_cache.write().unwrap();
@@ -44,7 +86,6 @@ mod tests {
     use std::fs;
     use std::path::Path;
     use serde::Deserialize;
-    use toml;
 
     #[derive(Debug, Deserialize)]
     #[allow(dead_code)]
@@ -66,28 +107,26 @@ mod tests {
     async fn test_cache_not_existing() {
         let secrets = load_secrets();
 
-        let state = web::Data::new(AppState { 
-            playlist_cache: Arc::new(RwLock::new(vec![])),
-            pool: PgPool::connect_lazy(&secrets.DATABASE_URL).unwrap(), // Lazy connection, 
-        });
+        let state = web::Data::new(AppState::new(
+            PgPool::connect_lazy(&secrets.DATABASE_URL).unwrap(), // Lazy connection, 
+        ));
         state.update_playlist_cache(vec![]);
-        assert!(state.is_playlist_cache_empty() == true);
+        assert!(state.is_playlist_cache_empty());
     }
 
     #[tokio::test]
     async fn test_cache_storing() {
         let secrets = load_secrets();
 
-        let state = web::Data::new(AppState { 
-            playlist_cache: Arc::new(RwLock::new(vec![])),
-            pool: PgPool::connect_lazy(&secrets.DATABASE_URL).unwrap(), // Lazy connection, 
-        });
+        let state = web::Data::new(AppState::new(
+            PgPool::connect_lazy(&secrets.DATABASE_URL).unwrap(), // Lazy connection, 
+        ));
 
 
         let test_cache = vec![
-            Song{id:0,artist:"artiste 1".to_string(),title:"Chanson A".to_string(),lyrics_url:"test".to_string(),singer:None},
-            Song{id:0,artist:"artiste 2".to_string(),title:"Chanson B".to_string(),lyrics_url:"test".to_string(),singer:None},
-            Song{id:0,artist:"artiste 3".to_string(),title:"Chanson C".to_string(),lyrics_url:"test".to_string(),singer:None},
+            Song { lyrics_url: "test".to_string(), ..Song::for_test(0, "artiste 1", "Chanson A") },
+            Song { lyrics_url: "test".to_string(), ..Song::for_test(0, "artiste 2", "Chanson B") },
+            Song { lyrics_url: "test".to_string(), ..Song::for_test(0, "artiste 3", "Chanson C") },
         ];
         state.update_playlist_cache(test_cache.clone());
         assert!(state.read_from_cache().unwrap() == test_cache);
@@ -97,18 +136,17 @@ mod tests {
     async fn test_cache_existing() {
         let secrets = load_secrets();
 
-        let state = web::Data::new(AppState { 
-            playlist_cache: Arc::new(RwLock::new(vec![])),
-            pool: PgPool::connect_lazy(&secrets.DATABASE_URL).unwrap(), // Lazy connection, 
-        });
+        let state = web::Data::new(AppState::new(
+            PgPool::connect_lazy(&secrets.DATABASE_URL).unwrap(), // Lazy connection, 
+        ));
 
 
         let test_cache = vec![
-            Song{id:0,artist:"artiste 1".to_string(),title:"Chanson A".to_string(),lyrics_url:"test".to_string(),singer:None},
-            Song{id:0,artist:"artiste 2".to_string(),title:"Chanson B".to_string(),lyrics_url:"test".to_string(),singer:None},
-            Song{id:0,artist:"artiste 3".to_string(),title:"Chanson C".to_string(),lyrics_url:"test".to_string(),singer:None},
+            Song { lyrics_url: "test".to_string(), ..Song::for_test(0, "artiste 1", "Chanson A") },
+            Song { lyrics_url: "test".to_string(), ..Song::for_test(0, "artiste 2", "Chanson B") },
+            Song { lyrics_url: "test".to_string(), ..Song::for_test(0, "artiste 3", "Chanson C") },
         ];
         state.update_playlist_cache(test_cache.clone());
-        assert!(state.is_playlist_cache_empty() == false);
+        assert!(!state.is_playlist_cache_empty());
     }
 }
@@ -1,15 +1,34 @@
 use serde::{Deserialize, Serialize};
-use actix_web::{web, post, Responder, HttpResponse};
-use sqlx::FromRow;
+use actix_web::{web, get, post, HttpRequest, Responder, HttpResponse};
+use actix_web::middleware::from_fn;
+use chrono::NaiveDateTime;
+use sqlx::{FromRow, PgConnection};
 use crate::state::AppState; 
+use crate::auth::require_admin;
+use crate::events::LiveEvent;
+use crate::local_catalog::ProposedSong;
+use crate::catalog::{normalize, song_key};
+use crate::moderation::{bad_request, rate_limit, voter_id, InvalidInput, Moderation, MAX_COMMENT_LENGTH, MAX_SONG_FIELD_LENGTH};
 use serde_json::json;
 
 
 
-#[post("/add-suggestion")]
-async fn add_suggestion(suggestion: web::Json<Suggestion>, state: web::Data<AppState>) -> impl Responder {
+#[post("/add-suggestion", wrap = "from_fn(rate_limit)")]
+async fn add_suggestion(req: HttpRequest, suggestion: web::Json<Suggestion>, state: web::Data<AppState>) -> impl Responder {
 
-    let suggestion = suggestion.into_inner().insert_suggestion_into_db(state).await   ;
+    let checked = match Moderation::load(state.clone()).await {
+        Ok(moderation) => suggestion.check(&moderation),
+        Err(error) => return HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    };
+    if let Err(invalid) = checked {
+        return bad_request(&invalid);
+    }
+
+    let voter = voter_id(&req, &state.trusted_proxies);
+    let suggestion = suggestion.into_inner().insert_suggestion_into_db(&voter, state).await   ;
     
     match suggestion {
         Ok(content) => HttpResponse::Ok().json(json!({
@@ -24,25 +43,213 @@ async fn add_suggestion(suggestion: web::Json<Suggestion>, state: web::Data<AppS
 
 }
 
+// Songs asked by the guests that are still open, most wanted first
+#[get("/requested-songs")]
+async fn requested_songs(state: web::Data<AppState>) -> impl Responder {
+
+    match RequestedSong::load_open(state).await {
+        Ok(songs) => HttpResponse::Ok().json(songs),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+#[post("/vote-suggestion", wrap = "from_fn(rate_limit)")]
+async fn vote_suggestion(req: HttpRequest, vote: web::Json<SuggestionVote>, state: web::Data<AppState>) -> impl Responder {
+
+    let voter = voter_id(&req, &state.trusted_proxies);
+    match vote.into_inner().apply(&voter, state).await {
+        Ok(Some(song)) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": song,
+        })),
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "status": "ko",
+            "content": "Cette suggestion n'est plus ouverte aux votes.",
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+// Suggestions for the maestro, most voted then newest first, with the number of unread ones
+#[get("/suggestions", wrap = "from_fn(require_admin)")]
+async fn suggestion_inbox(filter: web::Query<SuggestionFilter>, state: web::Data<AppState>) -> impl Responder {
+
+    match SuggestionInbox::load(filter.status, state).await {
+        Ok(inbox) => HttpResponse::Ok().json(inbox),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+#[post("/change-suggestion-status", wrap = "from_fn(require_admin)")]
+async fn change_suggestion_status(change: web::Json<SuggestionStatusChange>, state: web::Data<AppState>) -> impl Responder {
 
-#[derive(Clone, PartialEq, Serialize, Deserialize, FromRow)]
+    match change.into_inner().apply(state).await {
+        Ok(Some(suggestion)) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": suggestion,
+        })),
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "status": "ko",
+            "content": "suggestion not found",
+        })),
+        // Reopened while the same song was asked again
+        Err(error) if error.as_database_error().is_some_and(|error| error.is_unique_violation()) => HttpResponse::Conflict().json(json!({
+            "status": "ko",
+            "code": "already_open",
+            "content": "Cette chanson a déjà été suggérée à nouveau, la suggestion ouverte reçoit les votes.",
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+#[post("/delete-suggestion", wrap = "from_fn(require_admin)")]
+async fn delete_suggestion(suggestion: web::Json<Suggestion>, state: web::Data<AppState>) -> impl Responder {
+
+    match suggestion.into_inner().delete_suggestion_from_db(state).await {
+        Ok(true) => HttpResponse::Ok().json(json!({
+            "status": "ok",
+            "content": "one suggestion deleted",
+        })),
+        Ok(false) => HttpResponse::NotFound().json(json!({
+            "status": "ko",
+            "content": "suggestion not found",
+        })),
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "status": "ko",
+            "content": error.to_string(),
+        })),
+    }
+}
+
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, FromRow, Debug)]
 pub struct Suggestion {
     pub id: i32,
-    pub content: String,
+    pub content: String, // Free text, or a comment next to the artist and title
+    #[serde(default)]
+    #[sqlx(default)]
+    pub artist: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub title: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub votes: i32, // Guests who asked for the same song, set by the server
+    #[serde(default)]
+    #[sqlx(default)]
+    pub status: Option<String>, // See SuggestionStatus, set by the server
+    #[serde(default)]
+    #[sqlx(default)]
+    pub created_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub proposed_song: Option<ProposedSong>, // Guessed for the maestro in the inbox
+}
+
+/// Moderation of a suggestion, the new ones are the unread ones
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionStatus {
+    New,
+    Handled,
+    Rejected,
+    /// The song was added to the catalog
+    Fulfilled,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct SuggestionFilter {
+    pub status: Option<SuggestionStatus>, // Every suggestion when missing
+}
 
+/// Public view of a song suggestion, without the comment of the guest
+#[derive(Clone, PartialEq, Serialize, Deserialize, FromRow, Debug)]
+pub struct RequestedSong {
+    pub id: i32,
+    pub artist: String,
+    pub title: String,
+    pub votes: i32,
 }
 
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SuggestionVote {
+    pub id: i32,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SuggestionStatusChange {
+    pub id: i32,
+    pub status: SuggestionStatus,
+}
+
+/// Suggestions shown to the maestro and the count of each status, whatever the filter
+#[derive(Clone, PartialEq, Serialize, Debug, Default)]
+pub struct SuggestionInbox {
+    pub suggestions: Vec<Suggestion>,
+    pub unread: i64,
+    pub handled: i64,
+    pub rejected: i64,
+    pub fulfilled: i64,
+}
+
+impl SuggestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionStatus::New => "new",
+            SuggestionStatus::Handled => "handled",
+            SuggestionStatus::Rejected => "rejected",
+            SuggestionStatus::Fulfilled => "fulfilled",
+        }
+    }
+}
+
+const SUGGESTION_COLUMNS: &str = "id, COALESCE(content, '') AS content, artist, title, votes, status, created_at";
+// Suggestions the guests can still vote for
+const OPEN_SONGS: &str = "suggestion_key IS NOT NULL AND status IN ('new', 'handled')";
+
 impl Suggestion {
-    pub async fn insert_suggestion_into_db(&self, state: web::Data<AppState>) -> Result<Suggestion, sqlx::Error>{
-        sqlx::query_as("INSERT INTO suggestions(content) VALUES ($1) RETURNING id, content")
-            .bind(&self.content)
-            .fetch_one(&state.pool).await
+    /// The same song sent again is grouped with the open suggestion, which gets the vote of the guest
+    pub async fn insert_suggestion_into_db(&self, voter: &str, state: web::Data<AppState>) -> Result<Suggestion, sqlx::Error>{
+        let song = self.song();
+        let key = song.as_ref().map(|song| song_key(&song.artist, &song.title));
+        let song = song.unwrap_or_default();
+        let mut tx = state.pool.begin().await?;
+        // The no-op update locks the open suggestion until its votes are counted
+        let (id,): (i32,) = sqlx::query_as(
+                "INSERT INTO suggestions(content, artist, title, suggestion_key) VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (suggestion_key) WHERE status IN ('new', 'handled') DO UPDATE SET votes = suggestions.votes
+                 RETURNING id")
+            .bind(self.content.trim())
+            .bind(&song.artist)
+            .bind(&song.title)
+            .bind(key)
+            .fetch_one(&mut *tx).await?;
+        add_vote(id, voter, &mut tx).await?;
+        let suggestion = sqlx::query_as(&format!("SELECT {} FROM suggestions WHERE id = $1", SUGGESTION_COLUMNS))
+            .bind(id)
+            .fetch_one(&mut *tx).await?;
+        tx.commit().await?;
+        state.notify(LiveEvent::Suggestions);
+        Ok(suggestion)
     }
 
-    pub async fn _delete_suggestion_from_playlist(&self, state: web::Data<AppState>) -> Result<bool, sqlx::Error>
+    pub async fn delete_suggestion_from_db(&self, state: web::Data<AppState>) -> Result<bool, sqlx::Error>
     {
         let result = sqlx::query("DELETE FROM suggestions WHERE id = $1")
-            .bind(&self.id)
+            .bind(self.id)
             .execute(&state.pool)
             .await; 
 
@@ -50,17 +257,304 @@ impl Suggestion {
             Ok(query_result) => {
                 // Check if any rows were affected
                 let rows_affected = query_result.rows_affected();
+                if rows_affected > 0 {
+                    state.notify(LiveEvent::Suggestions);
+                }
                 Ok(rows_affected > 0) // Returns true if at least one row was deleted
             }
             Err(e) => Err(e), // Propagate the error
         }        
     }
 
+    /// A song or a message is needed, both are checked like the other guest inputs
+    pub fn check(&self, moderation: &Moderation) -> Result<(), InvalidInput> {
+        if self.title.trim().is_empty() && self.content.trim().is_empty() {
+            return Err(InvalidInput::Empty);
+        }
+        moderation.check("L'artiste", &self.artist, MAX_SONG_FIELD_LENGTH, false)?;
+        moderation.check("Le titre", &self.title, MAX_SONG_FIELD_LENGTH, false)?;
+        moderation.check("Le message", &self.content, MAX_COMMENT_LENGTH, true)
+    }
+
+    /// The song asked for, from the fields or else from an "Artist - Title" text.
+    /// None for the free text suggestions, which are never grouped.
+    fn song(&self) -> Option<ProposedSong> {
+        let song = if self.title.trim().is_empty() {
+            Some(ProposedSong::parse(&self.content)).filter(|song| !song.artist.is_empty())
+        } else {
+            Some(ProposedSong { artist: self.artist.trim().to_string(), title: self.title.trim().to_string() })
+        };
+        song.filter(|song| !normalize(&song.title).is_empty())
+    }
+
+}
+
+impl RequestedSong {
+    pub async fn load_open(state: web::Data<AppState>) -> Result<Vec<RequestedSong>, sqlx::Error> {
+        sqlx::query_as(&format!(
+                "SELECT id, artist, title, votes FROM suggestions WHERE {} ORDER BY votes DESC, created_at DESC, id DESC",
+                OPEN_SONGS))
+            .fetch_all(&state.pool)
+            .await
+    }
+}
+
+impl SuggestionVote {
+    /// The song with its new count, None when it can't be voted for.
+    /// A guest voting again for the same song is not counted twice.
+    pub async fn apply(&self, voter: &str, state: web::Data<AppState>) -> Result<Option<RequestedSong>, sqlx::Error> {
+        let mut tx = state.pool.begin().await?;
+        let open: Option<(i32,)> = sqlx::query_as(&format!("SELECT id FROM suggestions WHERE id = $1 AND {} FOR UPDATE", OPEN_SONGS))
+            .bind(self.id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if open.is_none() {
+            return Ok(None);
+        }
+
+        add_vote(self.id, voter, &mut tx).await?;
+        let song = sqlx::query_as("SELECT id, artist, title, votes FROM suggestions WHERE id = $1")
+            .bind(self.id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        state.notify(LiveEvent::Suggestions);
+        Ok(Some(song))
+    }
+}
+
+/// Record the vote of the guest once and count the votes of the suggestion again
+async fn add_vote(id: i32, voter: &str, tx: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO suggestion_votes(suggestion_id, client_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(id)
+        .bind(voter)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE suggestions SET votes = (SELECT COUNT(*) FROM suggestion_votes WHERE suggestion_id = $1) WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    Ok(())
+}
+
+impl SuggestionStatusChange {
+    /// The updated suggestion, None when it does not exist
+    pub async fn apply(&self, state: web::Data<AppState>) -> Result<Option<Suggestion>, sqlx::Error> {
+        let suggestion = sqlx::query_as(&format!("UPDATE suggestions SET status = $1 WHERE id = $2 RETURNING {}", SUGGESTION_COLUMNS))
+            .bind(self.status.as_str())
+            .bind(self.id)
+            .fetch_optional(&state.pool)
+            .await?;
+        if suggestion.is_some() {
+            state.notify(LiveEvent::Suggestions);
+        }
+        Ok(suggestion)
+    }
+}
+
+impl SuggestionInbox {
+    pub async fn load(status: Option<SuggestionStatus>, state: web::Data<AppState>) -> Result<SuggestionInbox, sqlx::Error> {
+        let mut suggestions: Vec<Suggestion> = sqlx::query_as(&format!(
+                "SELECT {} FROM suggestions WHERE $1::TEXT IS NULL OR status = $1 ORDER BY votes DESC, created_at DESC, id DESC",
+                SUGGESTION_COLUMNS))
+            .bind(status.map(|status| status.as_str()))
+            .fetch_all(&state.pool)
+            .await?;
+
+        for suggestion in suggestions.iter_mut() {
+            suggestion.proposed_song = Some(match suggestion.title.is_empty() {
+                true => ProposedSong::parse(&suggestion.content),
+                false => ProposedSong { artist: suggestion.artist.clone(), title: suggestion.title.clone() },
+            });
+        }
+
+        let (unread, handled, rejected, fulfilled): (i64, i64, i64, i64) = sqlx::query_as(
+                "SELECT COUNT(*) FILTER (WHERE status = 'new'),
+                        COUNT(*) FILTER (WHERE status = 'handled'),
+                        COUNT(*) FILTER (WHERE status = 'rejected'),
+                        COUNT(*) FILTER (WHERE status = 'fulfilled')
+                 FROM suggestions")
+            .fetch_one(&state.pool)
+            .await?;
+
+        Ok(SuggestionInbox { suggestions, unread, handled, rejected, fulfilled })
+    }
 }
 
 
-pub async fn _fetch_suggestions(state: web::Data<AppState>) -> Result<Vec<Suggestion>, sqlx::Error> {
-    sqlx::query_as("select * FROM suggestions")
-    .fetch_all(&state.pool)
-    .await
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use sqlx::PgPool;
+    use crate::auth::{test_admin_header, TEST_SECRET};
+    use crate::moderation::CLIENT_ID_HEADER;
+    use std::path::Path;
+    use std::fs;
+
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    #[allow(non_snake_case)]
+    struct Secrets {
+        pub DATABASE_URL: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct InboxResponse {
+        suggestions: Vec<Suggestion>,
+    }
+
+    fn suggestion(content: &str, artist: &str, title: &str) -> Suggestion {
+        Suggestion {
+            id: 0,
+            content: content.to_string(),
+            artist: artist.to_string(),
+            title: title.to_string(),
+            votes: 0,
+            status: None,
+            created_at: None,
+            proposed_song: None,
+        }
+    }
+
+    fn load_secrets() -> Secrets {
+        let content = fs::read_to_string(Path::new("Secrets.toml"))
+            .expect("Failed to read Secrets.toml");
+        toml::from_str(&content)
+            .expect("Failed to parse Secrets.toml")
+    }
+
+    #[actix_web::test]
+    async fn test_suggestion_inbox() {
+        let pool = PgPool::connect(&load_secrets().DATABASE_URL).await.expect("Fail to connect to Database");
+        let state = web::Data::new(AppState { jwt_secret: Some(TEST_SECRET.to_string()), ..AppState::new(pool) });
+        let app = test::init_service(App::new()
+            .app_data(state.clone())
+            .service(suggestion_inbox)
+            .service(change_suggestion_status)
+            .service(delete_suggestion)).await;
+
+        let first = suggestion("Daft Punk - Get Lucky", "", "")
+            .insert_suggestion_into_db("client guest 1", state.clone()).await.unwrap();
+        let second = suggestion("Plus de Queen !", "", "")
+            .insert_suggestion_into_db("client guest 1", state.clone()).await.unwrap();
+        assert_eq!(first.status.as_deref(), Some("new"));
+
+        // Newest first
+        let req = test::TestRequest::get().uri("/suggestions?status=new").insert_header(test_admin_header()).to_request();
+        let inbox: InboxResponse = test::call_and_read_body_json(&app, req).await;
+        let ids: Vec<i32> = inbox.suggestions.iter().map(|suggestion| suggestion.id).collect();
+        let second_index = ids.iter().position(|id| *id == second.id).unwrap();
+        assert!(ids[second_index + 1..].contains(&first.id));
+
+        let req = test::TestRequest::post()
+            .uri("/change-suggestion-status")
+            .insert_header(test_admin_header())
+            .set_json(SuggestionStatusChange { id: first.id, status: SuggestionStatus::Handled })
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+
+        // Other tests add suggestions at the same time, only the ones of this test are checked
+        let req = test::TestRequest::get().uri("/suggestions?status=handled").insert_header(test_admin_header()).to_request();
+        let inbox: InboxResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(inbox.suggestions.iter().any(|suggestion| suggestion.id == first.id));
+        let req = test::TestRequest::get().uri("/suggestions?status=new").insert_header(test_admin_header()).to_request();
+        let inbox: InboxResponse = test::call_and_read_body_json(&app, req).await;
+        assert!(!inbox.suggestions.iter().any(|suggestion| suggestion.id == first.id));
+        assert!(inbox.suggestions.iter().any(|suggestion| suggestion.id == second.id));
+
+        for suggestion in [&first, &second] {
+            let req = test::TestRequest::post().uri("/delete-suggestion").insert_header(test_admin_header()).set_json(suggestion).to_request();
+            assert!(test::call_service(&app, req).await.status().is_success());
+        }
+        let req = test::TestRequest::post().uri("/delete-suggestion").insert_header(test_admin_header()).set_json(&first).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_suggestion_votes() {
+        let pool = PgPool::connect(&load_secrets().DATABASE_URL).await.expect("Fail to connect to Database");
+        let state = web::Data::new(AppState { jwt_secret: Some(TEST_SECRET.to_string()), ..AppState::new(pool) });
+        let app = test::init_service(App::new()
+            .app_data(state.clone())
+            .service(requested_songs)
+            .service(vote_suggestion)
+            .service(delete_suggestion)).await;
+
+        // Same song written differently, grouped in one suggestion
+        let first = suggestion("Pour ma soeur", "Céline Dion", "Pour que tu m'aimes encore").insert_suggestion_into_db("client guest 1", state.clone()).await.unwrap();
+        let again = suggestion("", "  celine dion", "Pour que tu m’aimes encore !").insert_suggestion_into_db("client guest 2", state.clone()).await.unwrap();
+        let from_text = suggestion("CÉLINE DION - pour que tu m'aimes encore", "", "").insert_suggestion_into_db("client guest 3", state.clone()).await.unwrap();
+        assert_eq!(again.id, first.id);
+        assert_eq!(from_text.id, first.id);
+        assert_eq!(from_text.votes, 3);
+        assert_eq!(from_text.artist, "Céline Dion");
+
+        // The same guest asking again is counted once
+        let same_guest = suggestion("", "Céline Dion", "Pour que tu m'aimes encore").insert_suggestion_into_db("client guest 1", state.clone()).await.unwrap();
+        assert_eq!(same_guest.id, first.id);
+        assert_eq!(same_guest.votes, 3);
+
+        // Free text is never grouped nor voted for
+        let free_text = suggestion("Plus de Céline Dion", "", "").insert_suggestion_into_db("client guest 1", state.clone()).await.unwrap();
+        assert_ne!(free_text.id, first.id);
+        assert_eq!(free_text.title, "");
+
+        for _ in 0..2 {
+            let req = test::TestRequest::post()
+                .uri("/vote-suggestion")
+                .insert_header((CLIENT_ID_HEADER, "guest 4"))
+                .set_json(SuggestionVote { id: first.id })
+                .to_request();
+            assert!(test::call_service(&app, req).await.status().is_success());
+        }
+        let req = test::TestRequest::post().uri("/vote-suggestion").set_json(SuggestionVote { id: free_text.id }).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+        let req = test::TestRequest::get().uri("/requested-songs").to_request();
+        let songs: Vec<RequestedSong> = test::call_and_read_body_json(&app, req).await;
+        let song = songs.iter().find(|song| song.id == first.id).unwrap();
+        assert_eq!(song.votes, 4);
+        assert!(songs.windows(2).all(|pair| pair[0].votes >= pair[1].votes));
+        assert!(!songs.iter().any(|song| song.id == free_text.id));
+
+        for suggestion in [&first, &free_text] {
+            let req = test::TestRequest::post().uri("/delete-suggestion").insert_header(test_admin_header()).set_json(suggestion).to_request();
+            assert!(test::call_service(&app, req).await.status().is_success());
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_suggest_rejected_song_again() {
+        let pool = PgPool::connect(&load_secrets().DATABASE_URL).await.expect("Fail to connect to Database");
+        let state = web::Data::new(AppState { jwt_secret: Some(TEST_SECRET.to_string()), ..AppState::new(pool) });
+        let app = test::init_service(App::new()
+            .app_data(state.clone())
+            .service(change_suggestion_status)).await;
+
+        let rejected = suggestion("", "Las Ketchup", "Aserejé").insert_suggestion_into_db("client guest 1", state.clone()).await.unwrap();
+        SuggestionStatusChange { id: rejected.id, status: SuggestionStatus::Rejected }.apply(state.clone()).await.unwrap();
+
+        // A new open suggestion, the rejected one is left as it is
+        let again = suggestion("", "las ketchup", "Asereje").insert_suggestion_into_db("client guest 1", state.clone()).await.unwrap();
+        assert_ne!(again.id, rejected.id);
+        assert_eq!(again.status.as_deref(), Some("new"));
+        assert_eq!(again.votes, 1);
+        let again_twice = suggestion("", "Las Ketchup", "Aserejé").insert_suggestion_into_db("client guest 2", state.clone()).await.unwrap();
+        assert_eq!(again_twice.id, again.id);
+        assert_eq!(again_twice.votes, 2);
+
+        // Only one open suggestion per song
+        let req = test::TestRequest::post()
+            .uri("/change-suggestion-status")
+            .insert_header(test_admin_header())
+            .set_json(SuggestionStatusChange { id: rejected.id, status: SuggestionStatus::New })
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 409);
+
+        for suggestion in [&rejected, &again] {
+            suggestion.delete_suggestion_from_db(state.clone()).await.unwrap();
+        }
+    }
 }
@@ -0,0 +1,62 @@
+use gloo::storage::{LocalStorage, Storage};
+use gloo_net::http::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use web_sys::window;
+
+const SESSION_KEY: &str = "karaoke_admin_session";
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub token: String,
+    pub expires_at: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoginResponse {
+    pub status: String,
+    pub content: Session,
+}
+
+/// Current admin session, if one is stored and not expired
+pub fn get_session() -> Option<Session> {
+    let session: Session = LocalStorage::get(SESSION_KEY).ok()?;
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as u64;
+    if session.expires_at > now {
+        Some(session)
+    } else {
+        LocalStorage::delete(SESSION_KEY);
+        None
+    }
+}
+
+pub fn is_logged_in() -> bool {
+    get_session().is_some()
+}
+
+pub fn save_session(session: Session) {
+    if let Err(err) = LocalStorage::set(SESSION_KEY, session) {
+        web_sys::console::error_1(&format!("Failed to store session: {}", err).into());
+    }
+}
+
+/// Forget the session and reload the page so every component goes back to the guest view
+pub fn logout() {
+    LocalStorage::delete(SESSION_KEY);
+    if let Some(win) = window() {
+        let _ = win.location().reload();
+    }
+}
+
+/// Add the admin bearer token to a request to an admin only route
+pub fn with_auth(request: RequestBuilder) -> RequestBuilder {
+    match get_session() {
+        Some(session) => request.header("Authorization", &format!("Bearer {}", session.token)),
+        None => request,
+    }
+}
@@ -0,0 +1,22 @@
+use gloo::storage::{LocalStorage, Storage};
+use gloo_net::http::RequestBuilder;
+
+const CLIENT_ID_KEY: &str = "karaoke_client_id";
+
+
+/// Random id of this browser, kept across visits
+pub fn client_id() -> String {
+    if let Ok(id) = LocalStorage::get::<String>(CLIENT_ID_KEY) {
+        return id;
+    }
+    let id = format!("{:x}{:x}", (js_sys::Math::random() * 1e15) as u64, js_sys::Date::now() as u64);
+    if let Err(err) = LocalStorage::set(CLIENT_ID_KEY, &id) {
+        web_sys::console::error_1(&format!("Failed to store client id: {}", err).into());
+    }
+    id
+}
+
+/// Tell the server which guest sends a request, the guests of a venue often share one address
+pub fn with_client_id(request: RequestBuilder) -> RequestBuilder {
+    request.header("X-Client-Id", &client_id())
+}
@@ -0,0 +1,91 @@
+use yew::prelude::*;
+
+use crate::types::catalog_diff::CatalogDiff;
+use crate::types::song::Song;
+
+
+#[derive(Properties, PartialEq)]
+pub struct CatalogDiffReportProps {
+    pub diff: CatalogDiff,
+    pub on_close: Callback<()>,
+}
+
+fn song_label(song: &Song) -> String {
+    format!("{} - {}", song.artist, song.title)
+}
+
+/// Summary of a catalog refresh, so the maestro can fix the sheet before guests notice
+#[function_component(CatalogDiffReport)]
+pub fn catalog_diff_report(CatalogDiffReportProps { diff, on_close }: &CatalogDiffReportProps) -> Html {
+    let on_close = {
+        let on_close = on_close.clone();
+        Callback::from(move |_| on_close.emit(()))
+    };
+
+    let song_items = |songs: &Vec<Song>| -> Html {
+        songs.iter().map(|song| html! { <li>{ song_label(song) }</li> }).collect()
+    };
+
+    html! {
+        <div class="popup">
+            <div class="popup-content catalog-report">
+                <h3>{ "Liste de chansons mise à jour" }</h3>
+                if diff.is_empty() {
+                    <p>{ "Aucun changement." }</p>
+                }
+                if !diff.added.is_empty() {
+                    <h4>{ format!("Ajoutées ({})", diff.added.len()) }</h4>
+                    <ul>{ song_items(&diff.added) }</ul>
+                }
+                if !diff.removed.is_empty() {
+                    <h4>{ format!("Retirées ({})", diff.removed.len()) }</h4>
+                    <ul>{ song_items(&diff.removed) }</ul>
+                }
+                if !diff.changed.is_empty() {
+                    <h4>{ format!("Modifiées ({})", diff.changed.len()) }</h4>
+                    <ul>
+                        {
+                            for diff.changed.iter().map(|change| {
+                                let lyrics = if change.before.lyrics_url != change.after.lyrics_url { " (lien des paroles)" } else { "" };
+                                html! {
+                                    <li>{ format!("{} → {}{}", song_label(&change.before), song_label(&change.after), lyrics) }</li>
+                                }
+                            })
+                        }
+                    </ul>
+                }
+                if !diff.failed.is_empty() {
+                    <h4>{ format!("Feuilles non lues, leurs chansons sont gardées ({})", diff.failed.len()) }</h4>
+                    <ul>
+                        {
+                            for diff.failed.iter().map(|failed| html! {
+                                <li>{ format!("{} : {}", failed.source, failed.error) }</li>
+                            })
+                        }
+                    </ul>
+                }
+                if !diff.skipped.is_empty() {
+                    <h4>{ format!("Lignes ignorées ({})", diff.skipped.len()) }</h4>
+                    <ul>
+                        {
+                            for diff.skipped.iter().map(|skipped| {
+                                let reason = match skipped.reason.as_str() {
+                                    "duplicate" => "chanson en double",
+                                    _ => "titre ou artiste manquant",
+                                };
+                                let place = match &skipped.source {
+                                    Some(source) => format!("{}, ligne {}", source, skipped.row),
+                                    None => format!("Ligne {}", skipped.row),
+                                };
+                                html! { <li>{ format!("{} : {}", place, reason) }</li> }
+                            })
+                        }
+                    </ul>
+                }
+                <div class="popup-buttons">
+                    <button onclick={on_close}>{ "Ok" }</button>
+                </div>
+            </div>
+        </div>
+    }
+}
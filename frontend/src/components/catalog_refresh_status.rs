@@ -0,0 +1,94 @@
+use yew::prelude::*;
+use gloo_net::http::Request;
+use wasm_bindgen::JsValue;
+
+use crate::config::Config;
+use crate::auth;
+use crate::types::refresh_status::RefreshStatus;
+
+
+async fn get_refresh_status() -> Option<RefreshStatus> {
+    let config: Config = Config::load();
+    let url = format!("{}/catalog-refresh-status", config.backoffice_url);
+
+    match auth::with_auth(Request::get(&url)).send().await {
+        Ok(response) if response.ok() => response.json::<RefreshStatus>().await.ok(),
+        Ok(response) => {
+            web_sys::console::error_1(&format!("catalog-refresh-status error: {}", response.status()).into());
+            None
+        }
+        Err(err) => {
+            web_sys::console::error_1(&format!("Request send error: {}", err).into());
+            None
+        }
+    }
+}
+
+/// Date in the browser's format, "jamais" when missing
+pub fn format_date(date: &Option<String>) -> String {
+    match date {
+        Some(date) => js_sys::Date::new(&JsValue::from_str(date))
+            .to_locale_string("fr-FR", &JsValue::UNDEFINED)
+            .as_string()
+            .unwrap_or_else(|| date.clone()),
+        None => "jamais".to_string(),
+    }
+}
+
+/// Last automatic or manual refreshes of the song list
+#[function_component(CatalogRefreshStatus)]
+pub fn catalog_refresh_status() -> Html {
+    let status = use_state(|| None::<RefreshStatus>);
+
+    let load = {
+        let status = status.clone();
+        Callback::from(move |_: ()| {
+            let status = status.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                status.set(get_refresh_status().await);
+            });
+        })
+    };
+
+    {
+        let load = load.clone();
+        use_effect_with((), move |_| {
+            load.emit(());
+            || ()
+        });
+    }
+
+    let on_reload = Callback::from(move |_: MouseEvent| load.emit(()));
+
+    html! {
+        <div id="catalog_refresh_status">
+            {
+                match &*status {
+                    Some(status) => html! {
+                        <ul>
+                            if status.in_progress {
+                                <li>{ "Mise à jour en cours..." }</li>
+                            }
+                            <li>{ format!("Dernière mise à jour réussie : {}", format_date(&status.last_success)) }</li>
+                            if status.last_error.is_some() {
+                                <li>{ format!("Dernière erreur : {} ({})",
+                                    format_date(&status.last_error),
+                                    status.last_error_message.clone().unwrap_or_default()) }</li>
+                            }
+                            if status.consecutive_failures > 0 {
+                                <li>{ format!("Échecs consécutifs : {}", status.consecutive_failures) }</li>
+                            }
+                            if status.interval_minutes > 0 {
+                                <li>{ format!("Prochaine mise à jour : {}", format_date(&status.next_refresh)) }</li>
+                            } else {
+                                <li>{ "Mise à jour automatique désactivée." }</li>
+                            }
+                        </ul>
+                    },
+                    None => html! { <p>{ "Statut indisponible." }</p> },
+                }
+            }
+            <button onclick={on_reload}>{ "Rafraîchir" }</button>
+        </div>
+    }
+}
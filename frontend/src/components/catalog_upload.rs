@@ -0,0 +1,119 @@
+use crate::config::Config;
+use crate::auth;
+use crate::components::popup_confirm::PopupConfirm;
+use crate::components::catalog_diff_report::CatalogDiffReport;
+use crate::types::catalog_diff::CatalogDiff;
+
+use yew::prelude::*;
+use gloo_net::http::Request;
+use serde::Deserialize;
+
+
+/// Answer of the backend, `content` is the number of imported songs or the error.
+/// `diff` is only set when the file replaced the current catalog.
+#[derive(Deserialize)]
+struct UploadResponse {
+    content: serde_json::Value,
+    #[serde(default)]
+    diff: Option<CatalogDiff>,
+}
+
+/// Upload a CSV or JSON song catalog, used by the "local file" catalog source
+#[function_component(CatalogUpload)]
+pub fn catalog_upload() -> Html {
+    let file = use_state(|| None::<web_sys::File>);
+    let message = use_state(|| None::<String>);
+    let report = use_state(|| None::<CatalogDiff>);
+
+    let on_file_change = {
+        let file = file.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target_unchecked_into::<web_sys::HtmlInputElement>();
+            file.set(input.files().and_then(|files| files.get(0)));
+        })
+    };
+
+    let on_upload = {
+        let file = file.clone();
+        let message = message.clone();
+        let report = report.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(selected) = (*file).clone() else {
+                message.set(Some("Choisissez d'abord un fichier CSV ou JSON.".to_string()));
+                return;
+            };
+            let message = message.clone();
+            let report = report.clone();
+            let config: Config = Config::load();
+            let url = format!("{}/catalog-upload", config.backoffice_url);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let filename = selected.name();
+                let content_type = if filename.to_lowercase().ends_with(".json") { "application/json" } else { "text/csv" };
+                let content = match gloo::file::futures::read_as_text(&gloo::file::File::from(selected)).await {
+                    Ok(content) => content,
+                    Err(err) => {
+                        web_sys::console::error_1(&format!("Failed to read file: {}", err).into());
+                        message.set(Some("Impossible de lire le fichier.".to_string()));
+                        return;
+                    }
+                };
+
+                let response = auth::with_auth(Request::post(&url))
+                    .query([("filename", filename.as_str())])
+                    .header("Content-Type", content_type)
+                    .body(content)
+                    .unwrap()
+                    .send()
+                    .await;
+
+                let text = match response {
+                    Ok(response) => {
+                        let ok = response.ok();
+                        match response.json::<UploadResponse>().await {
+                            Ok(UploadResponse { diff: Some(diff), .. }) if ok => {
+                                report.set(Some(diff));
+                                return;
+                            }
+                            Ok(answer) if ok => format!("{} chansons importées.", answer.content),
+                            Ok(answer) => format!("Import refusé : {}", answer.content.as_str().unwrap_or_default()),
+                            Err(_) => "Import impossible, réessayez plus tard.".to_string(),
+                        }
+                    }
+                    Err(err) => {
+                        web_sys::console::error_1(&format!("Request send error: {}", err).into());
+                        "Import impossible, réessayez plus tard.".to_string()
+                    }
+                };
+                message.set(Some(text));
+            });
+        })
+    };
+
+    let on_close_popup = {
+        let message = message.clone();
+        Callback::from(move |_| message.set(None))
+    };
+
+    let on_close_report = {
+        let report = report.clone();
+        Callback::from(move |_| report.set(None))
+    };
+
+    html! {
+        <div id="catalog_upload">
+            <input type="file" accept=".csv,.json" onchange={on_file_change} />
+            <button onclick={on_upload}>{ "Importer" }</button>
+            {
+                if let Some(text) = &*message {
+                    html! { <PopupConfirm message={text.clone()} on_close={on_close_popup} /> }
+                } else {
+                    html! {}
+                }
+            }
+            if let Some(diff) = &*report {
+                <CatalogDiffReport diff={diff.clone()} on_close={on_close_report} />
+            }
+        </div>
+    }
+}
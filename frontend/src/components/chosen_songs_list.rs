@@ -1,11 +1,12 @@
 use gloo::timers::callback::Interval;
 use yew::prelude::*;
-use web_sys::window;
 use gloo_net::http::Request;
 use log::error;
 use crate::types::song::Song;
 use crate::config::Config; 
+use crate::auth;
 use crate::components::popup_delete_song::PopupDeleteSong;
+use crate::components::lyrics_viewer::LyricsViewer;
 
 
 /// Refresh the chosen songs list by fetching from the server
@@ -40,23 +41,84 @@ pub fn refresh_chosen_songs(chosen_songs_list: UseStateHandle<Vec<Song>>) {
     });
 }
 
+/// Move a song of the queue to a new index (0 is the top) then refresh the list
+pub fn move_song(id: i32, index: usize, chosen_songs_list: UseStateHandle<Vec<Song>>) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let config = Config::load();
+        let url = format!("{}/move-song", config.backoffice_url);
+        let move_request = serde_json::json!({ "id": id, "index": index });
+
+        match auth::with_auth(Request::post(&url))
+            .header("Content-Type", "application/json")
+            .body(move_request.to_string())
+        {
+            Ok(request) => match request.send().await {
+                Ok(resp) => {
+                    if resp.ok() {
+                        refresh_chosen_songs(chosen_songs_list);
+                    } else {
+                        web_sys::console::error_1(&format!("Failed to move song: {:?}", resp).into());
+                    }
+                }
+                Err(err) => {
+                    web_sys::console::error_1(&format!("Network error: {}", err).into());
+                }
+            },
+            Err(err) => {
+                web_sys::console::error_1(&format!("Failed to create request: {}", err).into());
+            }
+        }
+    });
+}
+
+/// End the current song (sung or skipped), start the next one then refresh the list
+pub fn next_song(skipped: bool, chosen_songs_list: UseStateHandle<Vec<Song>>) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let config = Config::load();
+        let url = format!("{}/next-song", config.backoffice_url);
+        let next_request = serde_json::json!({ "skipped": skipped });
+
+        match auth::with_auth(Request::post(&url))
+            .header("Content-Type", "application/json")
+            .body(next_request.to_string())
+        {
+            Ok(request) => match request.send().await {
+                Ok(resp) => {
+                    if resp.ok() {
+                        refresh_chosen_songs(chosen_songs_list);
+                    } else {
+                        web_sys::console::error_1(&format!("Failed to start next song: {:?}", resp).into());
+                    }
+                }
+                Err(err) => {
+                    web_sys::console::error_1(&format!("Network error: {}", err).into());
+                }
+            },
+            Err(err) => {
+                web_sys::console::error_1(&format!("Failed to create request: {}", err).into());
+            }
+        }
+    });
+}
+
 #[derive(Properties, PartialEq)]
 pub struct ChosenSongsListProps {
     pub refresh_trigger: UseStateHandle<bool>,
-    pub jukebox : bool
+    pub jukebox : bool,
+    /// The singers' turns decide the order, the songs can't be moved
+    #[prop_or_default]
+    pub round_robin : bool,
+    /// Live updates are connected, polling is only needed without them
+    pub live : bool
 }
 
 
 #[function_component(ChosenSongsList)]
-pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox }: &ChosenSongsListProps) -> Html {
-    let location = window()
-    .and_then(|win: web_sys::Window| win.location().pathname().ok()) // Get the path portion of the URL
-    .unwrap_or_else(|| "/".to_string()); // Default to "/" if retrieval fails
-    
+pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox, round_robin, live }: &ChosenSongsListProps) -> Html {
     let selected_song_to_delete = use_state(|| None);
+    let show_lyrics = use_state(|| false); // Lyrics of the song being performed
 
-    // Check if the current URL contains "/admin"
-    let is_admin_page = location.contains("/maestro");
+    let is_admin = auth::is_logged_in();
 
 
     let chosen_songs_list = use_state(|| vec![]);
@@ -98,16 +160,15 @@ pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox }: &Chos
        
     };
     {
-        let on_refresh_click = on_refresh_click.clone();
-        use_effect_with((),move |_| {
-            let interval = Interval::new(60000,
-                move || {
+        // Polling fallback while the live updates are disconnected
+        let chosen_songs_list = chosen_songs_list_callback.clone();
+        use_effect_with(*live, move |live| {
+            let interval = (!*live).then(|| {
+                Interval::new(60000, move || {
                     web_sys::console::log_1(&"Interval!".into());
-                    //refresh_chosen_songs(chosen_songs_list);
-                    let event = MouseEvent::new("click").unwrap(); // Create a default MouseEvent
-                    on_refresh_click.emit(event); // Emit MouseEvent
-                }
-            );
+                    refresh_chosen_songs(chosen_songs_list.clone());
+                })
+            });
 
             // Return a cleanup function to stop the interval when the component unmounts
             move || { drop(interval);}
@@ -134,7 +195,7 @@ pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox }: &Chos
                     let config = Config::load();
                     let url = format!("{}/delete-song", config.backoffice_url);
 
-                    match Request::post(&url)
+                    match auth::with_auth(Request::post(&url))
                         .header("Content-Type", "application/json")
                         .body(serde_json::to_string(&song).unwrap())
                     {
@@ -167,7 +228,28 @@ pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox }: &Chos
     };
 
 
-    let mut cpt = 0;
+    // Only queued songs are numbered and can be moved, the song being performed stays on top
+    let mut cpt: usize = 0;
+    let queue_length = chosen_songs_list_callback.iter().filter(|song| !song.is_playing()).count();
+    let is_playing = chosen_songs_list_callback.iter().any(|song| song.is_playing());
+
+    let on_next = {
+        let chosen_songs_list = chosen_songs_list_callback.clone();
+        Callback::from(move |_: MouseEvent| next_song(false, chosen_songs_list.clone()))
+    };
+    let on_skip = {
+        let chosen_songs_list = chosen_songs_list_callback.clone();
+        Callback::from(move |_: MouseEvent| next_song(true, chosen_songs_list.clone()))
+    };
+
+    let on_show_lyrics = {
+        let show_lyrics = show_lyrics.clone();
+        Callback::from(move |_: MouseEvent| show_lyrics.set(true))
+    };
+    let hide_lyrics = {
+        let show_lyrics = show_lyrics.clone();
+        Callback::from(move |_| show_lyrics.set(false))
+    };
 
     let rows: Html = chosen_songs_list_callback.iter().map(|song| {
         let on_song_select = {
@@ -177,6 +259,37 @@ pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox }: &Chos
                 on_click.emit(song.clone())
             })
         };
+        if song.is_playing() {
+            return html! {
+                <tr key={song.id.to_string()} class="now-playing">
+                    <td><i class="fa fa-microphone"></i></td>
+                    <td>{song.artist.clone()}</td>
+                    <td>
+                        {song.title.clone()}
+                        {" "}
+                        <button class="btn" onclick={on_show_lyrics.clone()}>
+                            { "Paroles" }
+                        </button>
+                    </td>
+                    if !jukebox {
+                        <td>{song.singer.clone().unwrap_or_else(|| "None".to_string())}</td>
+                    }
+                    if is_admin {
+                        <td>
+                            <button class="btn" onclick={on_song_select}>
+                                { "Supprimer" }
+                            </button>
+                        </td>
+                    }
+                </tr>
+            };
+        }
+        let index: usize = cpt;
+        let on_move = |new_index: usize| {
+            let id = song.id;
+            let chosen_songs_list = chosen_songs_list_callback.clone();
+            Callback::from(move |_: MouseEvent| move_song(id, new_index, chosen_songs_list.clone()))
+        };
         cpt += 1;
         html! {
             <tr key={song.id.to_string()}>
@@ -186,8 +299,19 @@ pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox }: &Chos
                 if !jukebox {
                     <td>{song.singer.clone().unwrap_or_else(|| "None".to_string())}</td>
                 }
-                if is_admin_page {
+                if is_admin {
                     <td>
+                        if !*round_robin {
+                            <button class="btn" title="Mettre en premier" disabled={index == 0} onclick={on_move(0)}>
+                                { "⤒" }
+                            </button>
+                            <button class="btn" title="Monter" disabled={index == 0} onclick={on_move(index.saturating_sub(1))}>
+                                { "↑" }
+                            </button>
+                            <button class="btn" title="Descendre" disabled={index + 1 == queue_length} onclick={on_move(index + 1)}>
+                                { "↓" }
+                            </button>
+                        }
                         <button class="btn" onclick={on_song_select}>
                             { "Supprimer" }
                         </button>
@@ -197,13 +321,26 @@ pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox }: &Chos
         }
     }).collect();
 
-    if cpt == 0 {
+    if chosen_songs_list_callback.is_empty() {
         html! {
             <p>{ "Aucune chanson sélectionnée" }</p>
         }
     } else {
         html! {
             <div class="w3-container">
+                if is_admin {
+                    <p>
+                        <button onclick={on_next} class="w3-green">
+                            { if is_playing { "Chanson suivante" } else { "Commencer" } }
+                        </button>
+                        if is_playing {
+                            {" "}
+                            <button onclick={on_skip} class="w3-orange">
+                                { "Passer" }
+                            </button>
+                        }
+                    </p>
+                }
 
                 <table class="w3-table w3-striped w3-white" id="chosen-song">
                         <thead class="w3-red">
@@ -214,7 +351,7 @@ pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox }: &Chos
                             if !jukebox {
                                 <th>{"Chanteur"}</th>
                             }
-                            if is_admin_page {
+                            if is_admin {
                                 <th>{"Action"}</th>
                             } 
                         </tr>
@@ -228,6 +365,9 @@ pub fn chosen_songs_list(ChosenSongsListProps { refresh_trigger,jukebox }: &Chos
                     { "Actualiser la liste de chansons ci-dessus." }
                 </button>
                 </p>
+                if *show_lyrics {
+                    <LyricsViewer song_id={None::<i32>} on_close={hide_lyrics} />
+                }
                 {
                     if let Some(_) = &*selected_song_to_delete {
                         html! {
@@ -0,0 +1,75 @@
+use crate::types::bo_config::BoConfig;
+
+use crate::config::Config;
+use crate::auth;
+use crate::components::config_text_input::get_text_config;
+
+use yew::prelude::*;
+use gloo_net::http::Request;
+
+
+#[derive(Properties, PartialEq)]
+pub struct ConfigSelectProps {
+    pub name: String,
+    pub options: Vec<(String, String)>, // (config value, label)
+}
+
+#[function_component(ConfigSelect)]
+pub fn config_select(ConfigSelectProps {name, options} : &ConfigSelectProps) -> Html {
+    let selected = use_state(|| String::new());
+
+    use_effect_with((), {
+        let name = name.clone();
+        let selected = selected.clone();
+        move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                let config = get_text_config(name.to_string()).await;
+                selected.set(config);
+            });
+            || ()
+        }
+    });
+
+    let on_change = {
+        let name = name.clone();
+        let selected = selected.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target_unchecked_into::<web_sys::HtmlSelectElement>();
+            let value = select.value();
+            selected.set(value.clone());
+
+            let bo_config = BoConfig {
+                id: 1,
+                name: name.to_string(),
+                value,
+            };
+
+            let config: Config = Config::load();
+            let url = format!("{}/change-config", config.backoffice_url);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let response = auth::with_auth(Request::post(&url))
+                    .header("Content-Type", "application/json")
+                    .json(&bo_config)
+                    .unwrap()
+                    .send()
+                    .await;
+
+                match response {
+                    Ok(_) => gloo::console::log!("Config updated successfully!"),
+                    Err(err) => gloo::console::error!(format!("Failed to update config: {err}")),
+                }
+            });
+        })
+    };
+
+    html! {
+        <select onchange={on_change}>
+            {
+                for options.iter().map(|(value, label)| html! {
+                    <option value={value.clone()} selected={*selected == *value}>{ label.clone() }</option>
+                })
+            }
+        </select>
+    }
+}
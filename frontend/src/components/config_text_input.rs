@@ -1,6 +1,7 @@
 use crate::types::bo_config::BoConfig; 
 
 use crate::config::Config; 
+use crate::auth;
 
 use yew::prelude::*;
 use gloo_net::http::Request;
@@ -77,7 +78,7 @@ pub fn toggle_button(ConfigTextInputProps {name} : &ConfigTextInputProps) -> Htm
                 let url = format!("{}/change-config", config.backoffice_url);
             
                 wasm_bindgen_futures::spawn_local(async move {
-                    let response = Request::post(&url)
+                    let response = auth::with_auth(Request::post(&url))
                         .header("Content-Type", "application/json")
                         .json(&bo_config)
                         .unwrap()
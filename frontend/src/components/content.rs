@@ -3,8 +3,8 @@ use serde::{Deserialize, Serialize};
 use wasm_bindgen_futures::spawn_local;
 use gloo_net::http::Request;
 use gloo_utils;
-use web_sys::window;
 use crate::config::Config;
+use crate::auth;
 use crate::components::popup_confirm::PopupConfirm;
 
 #[derive(Properties, PartialEq)]
@@ -12,6 +12,7 @@ pub struct Props {
     pub html: String,
 }
 
+/// Raw HTML of a content block, sanitized by the server when it is saved and read
 #[function_component(SafeHtml)]
 pub fn safe_html(props: &Props) -> Html {
     let div = gloo_utils::document().create_element("div").unwrap();
@@ -27,6 +28,46 @@ pub struct Content {
     pub content_text: String,
 }
 
+// Answer of `/add-content`, with the text as it was saved
+#[derive(Deserialize)]
+struct SavedContent {
+    content: Content,
+}
+
+
+/// Text of a content block, None when it can't be fetched
+pub async fn get_content(content_id: String) -> Option<Content> {
+    let content_to_retrieve = Content {
+        id : content_id,
+        content_text : "".to_string()
+    };
+    let config = Config::load();
+    let url = format!("{}/get-content", config.backoffice_url);
+    let request = match Request::post(&url)
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&content_to_retrieve).unwrap())
+    {
+        Ok(request) => request,
+        Err(_) => {
+            web_sys::console::error_1(&"Failed to create request.".into());
+            return None;
+        }
+    };
+    match request.send().await {
+        Ok(response) => match response.json::<Content>().await {
+            Ok(data) => Some(data),
+            Err(err) => {
+                web_sys::console::error_1(&format!("get-content JSON parse error: {}", err).into());
+                None
+            }
+        },
+        Err(err) => {
+            web_sys::console::error_1(&format!("Request send error: {}", err).into());
+            None
+        }
+    }
+}
+
 
 #[derive(Properties, PartialEq)]
 pub struct ContentComponentProps {
@@ -41,12 +82,7 @@ pub fn content_component(ContentComponentProps { content_id }: &ContentComponent
     let show_popup = use_state(|| false);
     let message: UseStateHandle<String> = use_state(|| "Votre suggestion a été enregistrée.".to_string());
 
-    let location = window()
-    .and_then(|win| win.location().pathname().ok()) // Get the path portion of the URL
-    .unwrap_or_else(|| "/".to_string()); // Default to "/" if retrieval fails
-
-    // Check if the current URL contains "/admin"
-    let logged_in = location.contains("/maestro");
+    let logged_in = auth::is_logged_in();
 
     {
         // Fetch content on mount (using an effect)
@@ -54,31 +90,9 @@ pub fn content_component(ContentComponentProps { content_id }: &ContentComponent
         let content_id = content_id.clone();
         use_effect_with((), move |_| {
                 spawn_local(async move {
-                    let content_to_retrieve = Content {
-                        id : content_id,
-                        content_text : "".to_string()
-                    };
-                    // Replace the URL with your backend endpoint
-                    let config = Config::load();
-                    let url = format!("{}/get-content", config.backoffice_url);
-                    if let Ok(request) = Request::post(&url)
-                    .header("Content-Type", "application/json")
-                    .body(serde_json::to_string(&content_to_retrieve).unwrap())
-                {
-                    match request.send().await {
-                        Ok(response) => match response.json::<Content>().await {
-                            Ok(data) => 
-                                {
-                                    content.set(Some(data));
-                                    web_sys::console::error_1(&format!("data added to content").into());
-                                },
-                            Err(err) => web_sys::console::error_1(&format!("get-content JSON parse error: {}", err).into()),
-                        },
-                        Err(err) => web_sys::console::error_1(&format!("Request send error: {}", err).into()),
+                    if let Some(data) = get_content(content_id).await {
+                        content.set(Some(data));
                     }
-                } else {
-                    web_sys::console::error_1(&"Failed to create request.".into());
-                }
                 });
                 || ()
             },
@@ -122,6 +136,7 @@ pub fn content_component(ContentComponentProps { content_id }: &ContentComponent
         let content_id = content_id.clone();
         let show_popup = show_popup.clone();
         let message = message.clone();
+        let content = content.clone();
 
         Callback::from(move |_| {
             web_sys::console::log_1(&format!("Saving: {}", *edit_text).into());
@@ -129,6 +144,7 @@ pub fn content_component(ContentComponentProps { content_id }: &ContentComponent
             let content_id = content_id.clone();
             let show_popup = show_popup.clone();
             let message = message.clone();
+            let content = content.clone();
     
             wasm_bindgen_futures::spawn_local(async move {
                 let config = Config::load();
@@ -139,7 +155,7 @@ pub fn content_component(ContentComponentProps { content_id }: &ContentComponent
                 };
                 web_sys::console::error_1(&format!("Content text to send: {:?}", input_value).into());
 
-                match Request::post(&url)
+                match auth::with_auth(Request::post(&url))
                     .header("Content-Type", "application/json")
                     .body(serde_json::to_string(&content_to_send).unwrap())
                 {
@@ -148,6 +164,10 @@ pub fn content_component(ContentComponentProps { content_id }: &ContentComponent
                             if resp.ok() {
                                 message.set("Contenu a mis à jour !".to_string());                   
                                 web_sys::console::log_1(&"Content successfully sent!".into());
+                                // The editor shows the text as the server cleaned it
+                                if let Ok(saved) = resp.json::<SavedContent>().await {
+                                    content.set(Some(saved.content));
+                                }
                             } else {
                                 message.set("Impossible de mettre à jour le contenu".to_string());                   
                                 web_sys::console::error_1(&format!("Failed to send Content: {:?}", resp).into());
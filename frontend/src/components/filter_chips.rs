@@ -0,0 +1,57 @@
+use crate::types::catalog_page::{decade_label, CatalogFilters, ChipSelection};
+
+use yew::prelude::*;
+
+
+#[derive(Properties, PartialEq)]
+pub struct FilterChipsProps {
+    pub filters: CatalogFilters,
+    pub selected: ChipSelection,
+    pub on_change: Callback<ChipSelection>,
+}
+
+/// Chips filtering the catalog on the details of the songs, a second click removes the filter
+#[function_component(FilterChips)]
+pub fn filter_chips(FilterChipsProps { filters, selected, on_change }: &FilterChipsProps) -> Html {
+    let chip = |label: String, active: bool, toggle: Box<dyn Fn(&mut ChipSelection)>| {
+        let on_change = on_change.clone();
+        let selected = selected.clone();
+        let onclick = Callback::from(move |_: MouseEvent| {
+            let mut new_selection = selected.clone();
+            toggle(&mut new_selection);
+            on_change.emit(new_selection);
+        });
+        html! {
+            <button class={classes!("chip", active.then_some("selected"))} {onclick}>{ label }</button>
+        }
+    };
+
+    // A chip of a text group selects its value, or clears it when already selected
+    let text_chip = |value: &String, current: &Option<String>, field: fn(&mut ChipSelection) -> &mut Option<String>| {
+        let value = value.clone();
+        let active = current.as_ref() == Some(&value);
+        chip(value.clone(), active, Box::new(move |selection| {
+            *field(selection) = if active { None } else { Some(value.clone()) };
+        }))
+    };
+
+    html! {
+        <div class="filter-chips">
+            {
+                for filters.decades.iter().map(|decade| {
+                    let decade = *decade;
+                    let active = selected.decade == Some(decade);
+                    chip(decade_label(decade), active, Box::new(move |selection| {
+                        selection.decade = if active { None } else { Some(decade) };
+                    }))
+                })
+            }
+            { for filters.languages.iter().map(|language| text_chip(language, &selected.language, |selection| &mut selection.language)) }
+            { for filters.genres.iter().map(|genre| text_chip(genre, &selected.genre, |selection| &mut selection.genre)) }
+            if filters.duets {
+                { chip("Duos".to_string(), selected.duet, Box::new(|selection| selection.duet = !selection.duet)) }
+            }
+            { for filters.difficulties.iter().map(|difficulty| text_chip(difficulty, &selected.difficulty, |selection| &mut selection.difficulty)) }
+        </div>
+    }
+}
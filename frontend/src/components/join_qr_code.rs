@@ -0,0 +1,48 @@
+use yew::prelude::*;
+use web_sys::window;
+
+use crate::components::config_text_input::get_text_config;
+use crate::config::Config;
+
+
+/// Address of the QR code image, `format` is "svg" or "png".
+/// `version` only changes the address so the browser fetches the image again.
+pub fn qr_code_url(format: &str, size: u32, version: u64) -> String {
+    let config = Config::load();
+    format!("{}/qr-code?format={}&size={}&v={}", config.backoffice_url, format, size, version)
+}
+
+/// Address guests open to join : the configured one, or the address of this site
+pub async fn get_join_url() -> String {
+    let configured = get_text_config("public_url".to_string()).await;
+    if !configured.trim().is_empty() {
+        return configured.trim().to_string();
+    }
+    window()
+        .and_then(|win| win.location().origin().ok())
+        .map(|origin| format!("{}/", origin))
+        .unwrap_or_default()
+}
+
+
+#[derive(Properties, PartialEq)]
+pub struct JoinQrCodeProps {
+    #[prop_or(160)]
+    pub size: u32,
+    #[prop_or_default]
+    pub version: u64, // Changed when the public address changes
+}
+
+/// QR code of the join address, rendered by the backend
+#[function_component(JoinQrCode)]
+pub fn join_qr_code(props: &JoinQrCodeProps) -> Html {
+    html! {
+        <img
+            class="join-qr-code"
+            src={qr_code_url("svg", props.size, props.version)}
+            width={props.size.to_string()}
+            height={props.size.to_string()}
+            alt="QR code pour rejoindre le karaoké"
+        />
+    }
+}
@@ -0,0 +1,108 @@
+use yew::prelude::*;
+use gloo_net::http::Request;
+use web_sys::window;
+use crate::auth::{save_session, Credentials, LoginResponse};
+use crate::config::Config;
+
+
+#[function_component(LoginForm)]
+pub fn login_form() -> Html {
+    let username: UseStateHandle<String> = use_state(|| "".to_string());
+    let password: UseStateHandle<String> = use_state(|| "".to_string());
+    let error_message: UseStateHandle<Option<String>> = use_state(|| None);
+
+    let on_username_input = {
+        let username = username.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target_unchecked_into::<web_sys::HtmlInputElement>();
+            username.set(input.value());
+        })
+    };
+
+    let on_password_input = {
+        let password = password.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target_unchecked_into::<web_sys::HtmlInputElement>();
+            password.set(input.value());
+        })
+    };
+
+    let on_submit = {
+        let username = username.clone();
+        let password = password.clone();
+        let error_message = error_message.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let credentials = Credentials {
+                username: (*username).clone(),
+                password: (*password).clone(),
+            };
+            let error_message = error_message.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let config = Config::load();
+                let url = format!("{}/login", config.backoffice_url);
+
+                match Request::post(&url)
+                    .header("Content-Type", "application/json")
+                    .body(serde_json::to_string(&credentials).unwrap())
+                {
+                    Ok(request) => match request.send().await {
+                        Ok(resp) if resp.ok() => match resp.json::<LoginResponse>().await {
+                            Ok(login) => {
+                                save_session(login.content);
+                                if let Some(win) = window() {
+                                    let _ = win.location().reload();
+                                }
+                            }
+                            Err(err) => {
+                                web_sys::console::error_1(&format!("login JSON parse error: {}", err).into());
+                                error_message.set(Some("Connexion impossible".to_string()));
+                            }
+                        },
+                        Ok(resp) => {
+                            web_sys::console::error_1(&format!("Login refused: {:?}", resp).into());
+                            error_message.set(Some("Identifiant ou mot de passe incorrect".to_string()));
+                        }
+                        Err(err) => {
+                            web_sys::console::error_1(&format!("Network error: {}", err).into());
+                            error_message.set(Some("Connexion impossible".to_string()));
+                        }
+                    },
+                    Err(err) => {
+                        web_sys::console::error_1(&format!("Failed to create request: {}", err).into());
+                    }
+                }
+            });
+        })
+    };
+
+    html! {
+        <div class="w3-container" id="login">
+            <h3>{ "Connexion maestro" }</h3>
+            <form onsubmit={on_submit}>
+                <p>
+                    <input
+                        type="text"
+                        placeholder="Identifiant"
+                        value={(*username).clone()}
+                        oninput={on_username_input}
+                    />
+                </p>
+                <p>
+                    <input
+                        type="password"
+                        placeholder="Mot de passe"
+                        value={(*password).clone()}
+                        oninput={on_password_input}
+                    />
+                </p>
+                <button type="submit">{ "Se connecter" }</button>
+            </form>
+            if let Some(message) = &*error_message {
+                <p class="w3-text-red">{ message.clone() }</p>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,235 @@
+use gloo::timers::callback::Interval;
+use yew::prelude::*;
+use gloo_net::http::Request;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::auth;
+use crate::types::lyrics::{Lyrics, LyricsFormat, LyricsRequest};
+
+
+// LRC lines shown before and after the one being sung
+const LINES_BEFORE: usize = 2;
+const LINES_AFTER: usize = 6;
+
+#[derive(Deserialize)]
+struct LyricsResponse {
+    status: String,
+    content: serde_json::Value,
+}
+
+
+// Lyrics of a catalog song, or of the song being performed when there is no id
+async fn get_lyrics(song_id: Option<i32>) -> Result<Lyrics, String> {
+    let config: Config = Config::load();
+    let url = match song_id {
+        Some(song_id) => format!("{}/songs/{}/lyrics", config.backoffice_url, song_id),
+        None => format!("{}/current-lyrics", config.backoffice_url),
+    };
+
+    let response = Request::get(&url).send().await.map_err(|err| err.to_string())?;
+    if response.ok() {
+        return response.json::<Lyrics>().await.map_err(|err| err.to_string());
+    }
+    match response.json::<LyricsResponse>().await {
+        Ok(error) => Err(error.content.as_str().unwrap_or_default().to_string()),
+        Err(_) => Err(format!("Erreur {}", response.status())),
+    }
+}
+
+async fn save_lyrics(request: LyricsRequest) -> Result<(), String> {
+    let config: Config = Config::load();
+    let url = format!("{}/change-lyrics", config.backoffice_url);
+
+    let response = auth::with_auth(Request::post(&url))
+        .header("Content-Type", "application/json")
+        .json(&request)
+        .map_err(|err| err.to_string())?
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    match response.json::<LyricsResponse>().await {
+        Ok(saved) if saved.status == "ok" => Ok(()),
+        Ok(error) => Err(error.content.as_str().unwrap_or_default().to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+
+#[derive(Properties, PartialEq)]
+pub struct LyricsViewerProps {
+    pub song_id: Option<i32>, // None for the song being performed
+    pub on_close: Callback<()>,
+}
+
+/// Lyrics kept on the server, following the song when they are timed (LRC),
+/// or the external link when the song has none. The maestro can edit them.
+#[function_component(LyricsViewer)]
+pub fn lyrics_viewer(props: &LyricsViewerProps) -> Html {
+    let lyrics = use_state(|| None::<Lyrics>);
+    let error = use_state(|| None::<String>);
+    let position_ms = use_state(|| None::<i64>); // Where the song is, for LRC lyrics
+    let editing = use_state(|| None::<String>); // Text being edited by the maestro
+    let is_admin = auth::is_logged_in();
+
+    let load = {
+        let lyrics = lyrics.clone();
+        let error = error.clone();
+        let song_id = props.song_id;
+        Callback::from(move |_: ()| {
+            let lyrics = lyrics.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match get_lyrics(song_id).await {
+                    Ok(loaded) => {
+                        error.set(None);
+                        lyrics.set(Some(loaded));
+                    }
+                    Err(message) => error.set(Some(message)),
+                }
+            });
+        })
+    };
+
+    {
+        let load = load.clone();
+        use_effect_with(props.song_id, move |_| {
+            load.emit(());
+            || ()
+        });
+    }
+
+    {
+        // The time elapsed when the lyrics were loaded, plus the time spent since
+        let position_ms = position_ms.clone();
+        use_effect_with((*lyrics).clone(), move |lyrics| {
+            let elapsed_ms = lyrics.as_ref().and_then(|lyrics| lyrics.elapsed_ms);
+            let interval = elapsed_ms.map(|elapsed_ms| {
+                let loaded_at = js_sys::Date::now();
+                position_ms.set(Some(elapsed_ms));
+                Interval::new(500, move || {
+                    position_ms.set(Some(elapsed_ms + (js_sys::Date::now() - loaded_at) as i64));
+                })
+            });
+            move || { drop(interval); }
+        });
+    }
+
+    let on_close = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |_: MouseEvent| on_close.emit(()))
+    };
+
+    let on_edit = {
+        let editing = editing.clone();
+        let lyrics = lyrics.clone();
+        Callback::from(move |_: MouseEvent| {
+            let content = lyrics.as_ref().and_then(|lyrics| lyrics.content.clone()).unwrap_or_default();
+            editing.set(Some(content));
+        })
+    };
+
+    let on_input = {
+        let editing = editing.clone();
+        Callback::from(move |e: InputEvent| {
+            let textarea = e.target_unchecked_into::<web_sys::HtmlTextAreaElement>();
+            editing.set(Some(textarea.value()));
+        })
+    };
+
+    let on_cancel_edit = {
+        let editing = editing.clone();
+        Callback::from(move |_: MouseEvent| editing.set(None))
+    };
+
+    let on_save = {
+        let editing = editing.clone();
+        let lyrics = lyrics.clone();
+        let error = error.clone();
+        let load = load.clone();
+        Callback::from(move |_: MouseEvent| {
+            let (Some(content), Some(song_id)) = ((*editing).clone(), lyrics.as_ref().map(|lyrics| lyrics.song_id)) else {
+                return;
+            };
+            let editing = editing.clone();
+            let error = error.clone();
+            let load = load.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match save_lyrics(LyricsRequest { song_id, content }).await {
+                    Ok(()) => {
+                        editing.set(None);
+                        load.emit(());
+                    }
+                    Err(message) => error.set(Some(message)),
+                }
+            });
+        })
+    };
+
+    let body = match (&*lyrics, &*editing) {
+        (_, Some(content)) => html! {
+            <>
+                <p>{ "Texte simple, ou format LRC avec les temps : [01:02.50]Paroles" }</p>
+                <textarea class="lyrics-editor" value={content.clone()} oninput={on_input} />
+            </>
+        },
+        (Some(lyrics), None) => match (lyrics.format, &lyrics.content) {
+            (Some(LyricsFormat::Lrc), _) if !lyrics.lines.is_empty() => {
+                let current = position_ms.and_then(|position_ms| lyrics.current_line(position_ms));
+                // The song being performed shows a window around the line being sung
+                let (first, last) = match (lyrics.elapsed_ms, current) {
+                    (Some(_), Some(current)) => (current.saturating_sub(LINES_BEFORE), current + LINES_AFTER),
+                    (Some(_), None) => (0, LINES_AFTER),
+                    (None, _) => (0, lyrics.lines.len()),
+                };
+                html! {
+                    <div class="lyrics-lines">
+                        {
+                            for lyrics.lines.iter().enumerate().skip(first).take(last + 1 - first).map(|(index, line)| html! {
+                                <p class={classes!((current == Some(index)).then_some("current"))}>{ &line.text }</p>
+                            })
+                        }
+                    </div>
+                }
+            }
+            (Some(_), Some(content)) => html! {
+                <div class="lyrics-text">{ content }</div>
+            },
+            _ => match &lyrics.url {
+                Some(url) => html! {
+                    <p>
+                        <a target="_blank" href={url.clone()}>{ "Voir les paroles sur un autre site" }</a>
+                    </p>
+                },
+                None => html! { <p>{ "Pas de paroles pour cette chanson." }</p> },
+            },
+        },
+        (None, None) => html! {},
+    };
+
+    html! {
+        <div class="popup">
+            <div class="popup-content lyrics-viewer">
+                if let Some(lyrics) = &*lyrics {
+                    <h3>{ format!("{} - {}", lyrics.artist, lyrics.title) }</h3>
+                }
+                if let Some(message) = &*error {
+                    <p class="w3-text-red">{ message }</p>
+                }
+                { body }
+                <div class="popup-buttons">
+                    <button onclick={on_close}>{ "Fermer" }</button>
+                    if is_admin && lyrics.is_some() {
+                        if editing.is_some() {
+                            <button onclick={on_cancel_edit}>{ "Annuler" }</button>
+                            <button onclick={on_save}>{ "Enregistrer" }</button>
+                        } else {
+                            <button onclick={on_edit}>{ "Modifier les paroles" }</button>
+                        }
+                    }
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -44,6 +44,7 @@ pub fn popup_add_song(props: &PopupProps) -> Html {
                 <input
                     type={input_type}
                     placeholder="Enter text"
+                    maxlength="40"
                     value={(*input_value).clone()}
                     oninput={on_input}
                 />
@@ -0,0 +1,77 @@
+use yew::prelude::*;
+
+use crate::types::suggestion::{PromoteSuggestion, Suggestion};
+
+#[derive(Properties, PartialEq)]
+pub struct PopupProps {
+    pub suggestion: Suggestion,
+    pub on_validate: Callback<PromoteSuggestion>,
+    pub on_cancel: Callback<()>,
+    #[prop_or_default]
+    pub error: Option<String>, // Refused by the server
+}
+
+/// Artist and title of the song added from a suggestion, guessed by the server and corrected by the maestro
+#[function_component(PopupPromoteSuggestion)]
+pub fn popup_promote_suggestion(props: &PopupProps) -> Html {
+    let proposed = props.suggestion.proposed_song.clone().unwrap_or_default();
+    let artist = use_state(|| proposed.artist.clone());
+    let title = use_state(|| proposed.title.clone());
+    let lyrics_url = use_state(String::new);
+
+    let on_input = |field: UseStateHandle<String>| {
+        Callback::from(move |e: InputEvent| {
+            let input = e.target_unchecked_into::<web_sys::HtmlInputElement>();
+            field.set(input.value());
+        })
+    };
+
+    let on_validate = {
+        let on_validate = props.on_validate.clone();
+        let suggestion_id = props.suggestion.id;
+        let artist = artist.clone();
+        let title = title.clone();
+        let lyrics_url = lyrics_url.clone();
+        Callback::from(move |_: MouseEvent| {
+            on_validate.emit(PromoteSuggestion {
+                suggestion_id,
+                artist: (*artist).clone(),
+                title: (*title).clone(),
+                lyrics_url: (*lyrics_url).clone(),
+            })
+        })
+    };
+
+    let on_cancel = {
+        let on_cancel = props.on_cancel.clone();
+        Callback::from(move |_: MouseEvent| on_cancel.emit(()))
+    };
+
+    html! {
+        <div class="popup">
+            <div class="popup-content">
+                <h3>{ "Ajouter au catalogue" }</h3>
+                <p class="suggestion-content">{ &props.suggestion.content }</p>
+                <p>
+                    <label>{ "Artiste : " }</label>
+                    <input type="text" value={(*artist).clone()} oninput={on_input(artist.clone())} />
+                </p>
+                <p>
+                    <label>{ "Titre : " }</label>
+                    <input type="text" value={(*title).clone()} oninput={on_input(title.clone())} />
+                </p>
+                <p>
+                    <label>{ "Lien des paroles (facultatif) : " }</label>
+                    <input type="text" value={(*lyrics_url).clone()} oninput={on_input(lyrics_url.clone())} />
+                </p>
+                if let Some(error) = &props.error {
+                    <p class="w3-text-red">{ error }</p>
+                }
+                <div class="popup-buttons">
+                    <button onclick={on_cancel}>{ "Annuler" }</button>
+                    <button onclick={on_validate} disabled={artist.trim().is_empty() || title.trim().is_empty()}>{ "Valider" }</button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,279 @@
+use crate::types::sheet_layout::{SheetLayout, SheetLayoutRequest};
+use crate::components::config_text_input::get_text_config;
+use crate::components::popup_confirm::PopupConfirm;
+
+use crate::config::Config;
+use crate::auth;
+
+use yew::prelude::*;
+use gloo_net::http::Request;
+use serde::Deserialize;
+
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    content: String,
+}
+
+async fn get_sheet_layout(sheet_id: String) -> Option<SheetLayout> {
+    let config: Config = Config::load();
+    let url = format!("{}/get-sheet-layout", config.backoffice_url);
+
+    let request = SheetLayoutRequest { sheet_id, layout: None };
+    let response = auth::with_auth(Request::post(&url))
+        .header("Content-Type", "application/json")
+        .json(&request)
+        .unwrap()
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.ok() => match response.json::<SheetLayout>().await {
+            Ok(layout) => Some(layout),
+            Err(err) => {
+                web_sys::console::error_1(&format!("get-sheet-layout JSON parse error: {}", err).into());
+                None
+            }
+        },
+        Ok(response) => {
+            web_sys::console::error_1(&format!("get-sheet-layout error: {}", response.status()).into());
+            None
+        }
+        Err(err) => {
+            web_sys::console::error_1(&format!("Request send error: {}", err).into());
+            None
+        }
+    }
+}
+
+
+// Same splitting as the backend : ids separated by commas or spaces
+fn split_sheet_ids(value: &str) -> Vec<String> {
+    let mut ids: Vec<String> = vec![];
+    for id in value.split(|c: char| c == ',' || c == ';' || c.is_whitespace()).filter(|id| !id.is_empty()) {
+        if !ids.iter().any(|known| known == id) {
+            ids.push(id.to_string());
+        }
+    }
+    ids
+}
+
+
+/// Range and column mapping of each configured Google Sheet
+#[function_component(SheetLayoutForm)]
+pub fn sheet_layout_form() -> Html {
+    let sheet_ids = use_state(Vec::<String>::new);
+    let sheet_id = use_state(String::new);
+    let layout = use_state(SheetLayout::default);
+    let tabs = use_state(String::new); // Edited as text, split on save
+    let message = use_state(|| None::<String>);
+
+    let load_layout = {
+        let sheet_id = sheet_id.clone();
+        let layout = layout.clone();
+        let tabs = tabs.clone();
+        Callback::from(move |id: String| {
+            let sheet_id = sheet_id.clone();
+            let layout = layout.clone();
+            let tabs = tabs.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Some(loaded) = get_sheet_layout(id.clone()).await {
+                    tabs.set(loaded.tabs.join(", "));
+                    layout.set(loaded);
+                }
+                sheet_id.set(id);
+            });
+        })
+    };
+
+    let load = {
+        let sheet_ids = sheet_ids.clone();
+        let sheet_id = sheet_id.clone();
+        let load_layout = load_layout.clone();
+        Callback::from(move |_: ()| {
+            let sheet_ids = sheet_ids.clone();
+            let current = (*sheet_id).clone();
+            let load_layout = load_layout.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let ids = split_sheet_ids(&get_text_config("google_sheet_id".to_string()).await);
+                // Stay on the sheet being edited when it is still configured
+                let id = if ids.contains(&current) { current } else { ids.first().cloned().unwrap_or_default() };
+                sheet_ids.set(ids);
+                load_layout.emit(id);
+            });
+        })
+    };
+
+    let on_sheet_change = {
+        let load_layout = load_layout.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target_unchecked_into::<web_sys::HtmlSelectElement>();
+            load_layout.emit(select.value());
+        })
+    };
+
+    let on_tabs_input = {
+        let tabs = tabs.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                tabs.set(input.value());
+            }
+        })
+    };
+
+    {
+        let load = load.clone();
+        use_effect_with((), move |_| {
+            load.emit(());
+            || ()
+        });
+    }
+
+    let on_reload = {
+        let load = load.clone();
+        Callback::from(move |_: MouseEvent| load.emit(()))
+    };
+
+    // Every text field of the layout is edited the same way
+    let on_text_input = |update: fn(&mut SheetLayout, String)| {
+        let layout = layout.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                let mut new_layout = (*layout).clone();
+                update(&mut new_layout, input.value());
+                layout.set(new_layout);
+            }
+        })
+    };
+
+    let on_header_change = {
+        let layout = layout.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target_unchecked_into::<web_sys::HtmlInputElement>();
+            let mut new_layout = (*layout).clone();
+            new_layout.has_header = input.checked();
+            layout.set(new_layout);
+        })
+    };
+
+    let on_save = {
+        let sheet_id = sheet_id.clone();
+        let layout = layout.clone();
+        let tabs = tabs.clone();
+        let message = message.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut new_layout = (*layout).clone();
+            new_layout.tabs = tabs.split(',').map(|tab| tab.trim().to_string()).filter(|tab| !tab.is_empty()).collect();
+            let request = SheetLayoutRequest {
+                sheet_id: (*sheet_id).clone(),
+                layout: Some(new_layout),
+            };
+            let message = message.clone();
+            let config: Config = Config::load();
+            let url = format!("{}/change-sheet-layout", config.backoffice_url);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let response = auth::with_auth(Request::post(&url))
+                    .header("Content-Type", "application/json")
+                    .json(&request)
+                    .unwrap()
+                    .send()
+                    .await;
+
+                let text = match response {
+                    Ok(response) if response.ok() => "Colonnes enregistrées, pensez à mettre à jour la liste des chansons.".to_string(),
+                    Ok(response) if response.status() == 400 => match response.json::<ErrorResponse>().await {
+                        Ok(error) => format!("Colonnes invalides : {}", error.content),
+                        Err(_) => "Colonnes invalides.".to_string(),
+                    },
+                    _ => "Impossible d'enregistrer les colonnes, réessayez plus tard.".to_string(),
+                };
+                message.set(Some(text));
+            });
+        })
+    };
+
+    let on_close_popup = {
+        let message = message.clone();
+        Callback::from(move |_| message.set(None))
+    };
+
+    html! {
+        <div id="sheet_layout_form">
+            <p>{ "Colonnes de la feuille " }
+                <select onchange={on_sheet_change}>
+                    {
+                        for sheet_ids.iter().map(|id| html! {
+                            <option value={id.clone()} selected={*id == *sheet_id}>{ id }</option>
+                        })
+                    }
+                </select>
+                <button onclick={on_reload}>{ "Recharger" }</button>
+            </p>
+            <ul>
+                <li>{"Nom de la liste (affiché aux invités) :"}
+                    <input type="text" size="20" value={layout.name.clone()}
+                        oninput={on_text_input(|layout, value| layout.name = value)} />
+                </li>
+                <li>{"Plage (ex : A:D ou 'Feuille 1'!A:F) :"}
+                    <input type="text" size="20" value={layout.range.clone()}
+                        oninput={on_text_input(|layout, value| layout.range = value)} />
+                </li>
+                <li>{"Onglets à lire, séparés par des virgules (vide = la plage ci-dessus) :"}
+                    <input type="text" size="30" value={(*tabs).clone()} oninput={on_tabs_input} />
+                </li>
+                <li>{"Première ligne = noms des colonnes :"}
+                    <input type="checkbox" checked={layout.has_header} onchange={on_header_change} />
+                </li>
+                <li>{"Titre (nom de colonne ou lettre) :"}
+                    <input type="text" size="20" value={layout.title_column.clone()}
+                        oninput={on_text_input(|layout, value| layout.title_column = value)} />
+                </li>
+                <li>{"Artiste (nom de colonne ou lettre) :"}
+                    <input type="text" size="20" value={layout.artist_column.clone()}
+                        oninput={on_text_input(|layout, value| layout.artist_column = value)} />
+                </li>
+                <li>{"Paroles (nom de colonne ou lettre, vide si aucune) :"}
+                    <input type="text" size="20" value={layout.lyrics_url_column.clone()}
+                        oninput={on_text_input(|layout, value| layout.lyrics_url_column = value)} />
+                </li>
+                <li>{"Colonnes facultatives, vides si la feuille ne les a pas :"}
+                    <ul>
+                        <li>{"Genre :"}
+                            <input type="text" size="15" value={layout.genre_column.clone()}
+                                oninput={on_text_input(|layout, value| layout.genre_column = value)} />
+                        </li>
+                        <li>{"Langue :"}
+                            <input type="text" size="15" value={layout.language_column.clone()}
+                                oninput={on_text_input(|layout, value| layout.language_column = value)} />
+                        </li>
+                        <li>{"Année :"}
+                            <input type="text" size="15" value={layout.year_column.clone()}
+                                oninput={on_text_input(|layout, value| layout.year_column = value)} />
+                        </li>
+                        <li>{"Duo (x, oui...) :"}
+                            <input type="text" size="15" value={layout.duet_column.clone()}
+                                oninput={on_text_input(|layout, value| layout.duet_column = value)} />
+                        </li>
+                        <li>{"Difficulté :"}
+                            <input type="text" size="15" value={layout.difficulty_column.clone()}
+                                oninput={on_text_input(|layout, value| layout.difficulty_column = value)} />
+                        </li>
+                        <li>{"Durée (3:45) :"}
+                            <input type="text" size="15" value={layout.duration_column.clone()}
+                                oninput={on_text_input(|layout, value| layout.duration_column = value)} />
+                        </li>
+                    </ul>
+                </li>
+            </ul>
+            <button onclick={on_save}>{ "Valider" }</button>
+            {
+                if let Some(text) = &*message {
+                    html! { <PopupConfirm message={text.clone()} on_close={on_close_popup} /> }
+                } else {
+                    html! {}
+                }
+            }
+        </div>
+    }
+}
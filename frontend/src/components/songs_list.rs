@@ -1,49 +1,89 @@
-use web_sys::window;
 use yew::prelude::*;
 use crate::types::song::Song;
 use gloo_net::http::Request;
 use log::error;
 use crate::config::Config; 
+use crate::auth;
+use crate::client;
 use crate::components::popup_add_song::PopupAddSong;
+use crate::components::popup_confirm::PopupConfirm;
+use crate::components::lyrics_viewer::LyricsViewer;
+use crate::components::catalog_diff_report::CatalogDiffReport;
+use crate::types::catalog_diff::CatalogDiff;
+use crate::types::catalog_page::{CatalogPage, ChipSelection};
+use crate::components::filter_chips::FilterChips;
+use serde::Deserialize;
+use gloo::timers::callback::Timeout;
 
 
-/// Refresh the chosen songs list by fetching from the server
-pub fn force_refresh_songs(chosen_songs_list: UseStateHandle<Vec<Song>>) {
+// Time without typing before the search is sent
+const SEARCH_DELAY_MS: u32 = 300;
+
+// Songs shown in a page of the list
+const PER_PAGE: usize = 50;
+
+// Letters of the index above the list, "#" gathers the names starting with a digit or a symbol
+const LETTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ#";
+
+
+/// Error answer of the backend, `content` is a message for the guest
+#[derive(Deserialize)]
+struct ErrorResponse {
+    content: String,
+}
+
+/// Answer of `/song-update`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UpdateResponse {
+    Report { content: CatalogDiff },
+    Error { content: String },
+}
+
+
+/// Refresh the catalog from its source, then show what changed
+pub fn force_refresh_songs(reload: Callback<()>, report: UseStateHandle<Option<CatalogDiff>>, error_message: UseStateHandle<Option<String>>) {
     wasm_bindgen_futures::spawn_local(async move {
         let config = Config::load();
         let url = format!("{}/song-update", config.backoffice_url);
         web_sys::console::log_1(&format!("force_refresh_songs").into());
 
 
-        match Request::get(&url)
+        match auth::with_auth(Request::get(&url))
             .send()
             .await
         {
-            Ok(_response) => {
-                refresh_songs(chosen_songs_list);
+            Ok(response) => {
+                let ok = response.ok();
+                match response.json::<UpdateResponse>().await {
+                    Ok(UpdateResponse::Report { content }) if ok => report.set(Some(content)),
+                    Ok(UpdateResponse::Error { content }) => error_message.set(Some(format!("Mise à jour impossible : {}", content))),
+                    _ => error_message.set(Some("Mise à jour impossible, réessayez plus tard.".to_string())),
+                }
+                reload.emit(());
             }
             Err(err) => {
                 error!("Failed to fetch: {:?}", err);
-                web_sys::console::log_1(&format!("Failed to parse JSON response").into());
-
+                error_message.set(Some("Mise à jour impossible, réessayez plus tard.".to_string()));
             }
         }
     });
 }
 
 
-/// Refresh the chosen songs list by fetching from the server
-pub fn refresh_songs(chosen_songs_list: UseStateHandle<Vec<Song>>) {
+/// Fetch a page of the catalog, `query` holds the page, sorting and filters
+pub fn refresh_songs(query: Vec<(&'static str, String)>, catalog_page: UseStateHandle<CatalogPage>) {
     wasm_bindgen_futures::spawn_local(async move {
         let config = Config::load();
         let url = format!("{}/song-data", config.backoffice_url);
         match Request::get(&url)
+            .query(query.iter().map(|(name, value)| (*name, value.as_str())))
             .send()
             .await
         {
             Ok(response) => {
-                if let Ok(fetched_songs) = response.json::<Vec<Song>>().await {
-                    chosen_songs_list.set(fetched_songs);
+                if let Ok(fetched_page) = response.json::<CatalogPage>().await {
+                    catalog_page.set(fetched_page);
                 } else {
                     error!("song-data : Failed to parse JSON response");
                 }
@@ -55,6 +95,29 @@ pub fn refresh_songs(chosen_songs_list: UseStateHandle<Vec<Song>>) {
     });
 }
 
+/// Ask the backend for the songs matching the query, accents and typos are tolerated there.
+/// The answer is dropped when a newer search was sent in the meantime.
+fn search_songs(query: String, results: UseStateHandle<Option<Vec<Song>>>, generation: std::rc::Rc<std::cell::RefCell<u32>>, sent: u32) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let config = Config::load();
+        let url = format!("{}/songs/search", config.backoffice_url);
+        match Request::get(&url)
+            .query([("q", query.as_str())])
+            .send()
+            .await
+        {
+            Ok(response) => match response.json::<Vec<Song>>().await {
+                Ok(songs) if *generation.borrow() == sent => results.set(Some(songs)),
+                Ok(_) => {}
+                Err(_) => error!("songs/search : Failed to parse JSON response"),
+            },
+            Err(err) => {
+                error!("Failed to fetch: {:?}", err);
+            }
+        }
+    });
+}
+
 #[derive(Properties, PartialEq)]
 pub struct SongListProps {
     pub on_add: Callback<()>, // Callback to notify parent
@@ -66,37 +129,71 @@ pub struct SongListProps {
 #[function_component(SongsList)]
 pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProps) -> Html {
     let search_query: UseStateHandle<String> = use_state(|| "".to_string());
+    let source_filter: UseStateHandle<Option<String>> = use_state(|| None);
+    let search_results: UseStateHandle<Option<Vec<Song>>> = use_state(|| None);
+    let search_timeout = use_mut_ref(|| None::<Timeout>);
+    let search_generation = use_mut_ref(|| 0u32);
     // State for sorting
     let sort_column = use_state(|| "artist".to_string()); // Sort by artist initially
     let sort_order = use_state(|| true); // true = ascending, false = descending
     let selected_song_to_add = use_state(|| None);
+    let lyrics_song_id: UseStateHandle<Option<i32>> = use_state(|| None); // Song whose lyrics are shown
+    let add_error_message: UseStateHandle<Option<String>> = use_state(|| None);
+    let update_report: UseStateHandle<Option<CatalogDiff>> = use_state(|| None);
 
-    let location = window()
-    .and_then(|win| win.location().pathname().ok()) // Get the path portion of the URL
-    .unwrap_or_else(|| "/".to_string()); // Default to "/" if retrieval fails
+    let page = use_state(|| 1usize);
+    let letter: UseStateHandle<Option<char>> = use_state(|| None);
+    let chips = use_state(ChipSelection::default);
+    let reload_count = use_state(|| 0u32); // Bumped to fetch the current page again
 
-    // Check if the current URL contains "/admin"
-    let is_admin_page = location.contains("/maestro");
+    let is_admin = auth::is_logged_in();
 
-    let songs_list = use_state(|| vec![]);
+    let catalog_page = use_state(CatalogPage::default);
     {
-        let songs_list = songs_list.clone();  
-        use_effect_with((), move |_| {
-            refresh_songs(songs_list.clone());
-        || ()
-        });
+        let catalog_page = catalog_page.clone();
+        use_effect_with(
+            (*page, (*sort_column).clone(), *sort_order, *letter, (*source_filter).clone(), (*chips).clone(), *reload_count),
+            move |(page, sort_column, sort_order, letter, source, chips, _)| {
+                let mut query = vec![
+                    ("page", page.to_string()),
+                    ("per_page", PER_PAGE.to_string()),
+                    ("sort", sort_column.clone()),
+                    ("order", if *sort_order { "asc" } else { "desc" }.to_string()),
+                ];
+                if let Some(letter) = letter {
+                    query.push(("letter", letter.to_string()));
+                }
+                if let Some(source) = source {
+                    query.push(("source", source.clone()));
+                }
+                query.extend(chips.query());
+                refresh_songs(query, catalog_page);
+                || ()
+            },
+        );
     }
-    let songs_list = songs_list.clone();
+
+    let reload = {
+        let reload_count = reload_count.clone();
+        Callback::from(move |_: ()| reload_count.set(*reload_count + 1))
+    };
 
     let admin_refresh_song = {
-        let songs_list = songs_list.clone();
+        let reload = reload.clone();
+        let update_report = update_report.clone();
+        let add_error_message = add_error_message.clone();
 
         Callback::from(move |_event : MouseEvent| {
             web_sys::console::log_1(&format!("on refresh click").into());
-            force_refresh_songs(songs_list.clone());
+            force_refresh_songs(reload.clone(), update_report.clone(), add_error_message.clone());
         })
     };
 
+    let hide_update_report = {
+        let update_report = update_report.clone();
+        Callback::from(move |_| update_report.set(None))
+    };
+
     let show_add_popup = {  
         let selected_song_to_add = selected_song_to_add.clone();
         Callback::from(move |song: Song| selected_song_to_add.set(Some(song)))
@@ -107,13 +204,25 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
         Callback::from(move |_| selected_song_to_add.set(None))
     };
 
+    let hide_lyrics = {
+        let lyrics_song_id = lyrics_song_id.clone();
+        Callback::from(move |_| lyrics_song_id.set(None))
+    };
+
+    let hide_add_error = {
+        let add_error_message = add_error_message.clone();
+        Callback::from(move |_| add_error_message.set(None))
+    };
+
     let on_add_validate = {
         let hide_add_popup = hide_add_popup.clone();
         let selected_song_to_add = selected_song_to_add.clone();
         let on_add = on_add.clone();
+        let add_error_message = add_error_message.clone();
 
         Callback::from(move |input: String| {
             let on_add = on_add.clone();
+            let add_error_message = add_error_message.clone();
 
             web_sys::console::log_1(&format!("Validated input: {}", input).into());
             if let Some(mut song) = (*selected_song_to_add).clone() {
@@ -127,7 +236,7 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
                     let url = format!("{}/add-song", config.backoffice_url);
                     let on_add = on_add.clone();
 
-                    match Request::post(&url)
+                    match client::with_client_id(Request::post(&url))
                         .header("Content-Type", "application/json")
                         .body(serde_json::to_string(&song).unwrap())
                     {
@@ -138,10 +247,17 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
                                     on_add.emit(()); // Notify parent
                                 } else {
                                     web_sys::console::error_1(&format!("Failed to send song: {:?}", resp).into());
+                                    // Refused songs come with a message for the guest
+                                    let message = match resp.json::<ErrorResponse>().await {
+                                        Ok(error) if resp.status() < 500 => error.content,
+                                        _ => "Impossible d'ajouter la chanson, réessayez plus tard.".to_string(),
+                                    };
+                                    add_error_message.set(Some(message));
                                 }
                             }
                             Err(err) => {
                                 web_sys::console::error_1(&format!("Network error: {}", err).into());
+                                add_error_message.set(Some("Impossible d'ajouter la chanson, réessayez plus tard.".to_string()));
                             }
                         },
                         Err(err) => {
@@ -158,38 +274,79 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
 
 
 
-    // Compute filtered songs dynamically
-    let filtered_songs = {
+    // Sources of the catalog, only worth a filter when several sheets are merged
+    let sources = catalog_page.sources.clone();
+
+    let on_source_change = {
+        let source_filter = source_filter.clone();
+        let page = page.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target_unchecked_into::<web_sys::HtmlSelectElement>();
+            let value = select.value();
+            source_filter.set(if value.is_empty() { None } else { Some(value) });
+            page.set(1);
+        })
+    };
+
+    let on_letter = |selected: Option<char>| {
+        let letter = letter.clone();
+        let page = page.clone();
+        Callback::from(move |_: MouseEvent| {
+            letter.set(selected);
+            page.set(1);
+        })
+    };
+
+    let on_chips_change = {
+        let chips = chips.clone();
+        let page = page.clone();
+        Callback::from(move |selection: ChipSelection| {
+            chips.set(selection);
+            page.set(1);
+        })
+    };
+
+    let go_to_page = |target: usize| {
+        let page = page.clone();
+        Callback::from(move |_: MouseEvent| page.set(target))
+    };
+
+    let on_search_input = {
         let search_query = search_query.clone();
-        let sorted_songs = songs_list.clone();
-        let sort_column = sort_column.clone();
-        let sort_order = sort_order.clone();
+        let search_results = search_results.clone();
+        Callback::from(move |e: InputEvent| {
+            let input = e.target_unchecked_into::<web_sys::HtmlInputElement>();
+            let query = input.value();
+            search_query.set(query.clone());
+
+            // A new letter cancels the search waiting to be sent
+            search_timeout.borrow_mut().take();
+            *search_generation.borrow_mut() += 1;
+            if query.trim().is_empty() {
+                search_results.set(None);
+                return;
+            }
 
-        let mut songs = (*sorted_songs)
+            let search_results = search_results.clone();
+            let generation = search_generation.clone();
+            let sent = *search_generation.borrow();
+            *search_timeout.borrow_mut() = Some(Timeout::new(SEARCH_DELAY_MS, move || {
+                search_songs(query, search_results, generation, sent);
+            }));
+        })
+    };
+
+    // The page sorted and filtered by the backend, or the search results best match first
+    let filtered_songs = match &*search_results {
+        Some(results) => results
             .iter()
-            .filter(|song| {
-                let query = search_query.to_lowercase();
-                song.artist.to_lowercase().contains(&query) || song.title.to_lowercase().contains(&query)
-            })
+            .filter(|song| source_filter.is_none() || song.source == *source_filter)
+            .filter(|song| chips.matches(song))
             .cloned()
-            .collect::<Vec<_>>();
-
-        // Sorting logic
-        songs.sort_by(|a, b| {
-            let cmp = match sort_column.as_str() {
-                "artist" => a.artist.to_lowercase().cmp(&b.artist.to_lowercase()),
-                "title" => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
-                _ => a.artist.to_lowercase().cmp(&b.artist.to_lowercase()), // default to artist
-            };
-            if *sort_order {
-                cmp
-            } else {
-                cmp.reverse() // reverse the order if descending
-            }
-        });
-
-        songs
+            .collect::<Vec<_>>(),
+        None => catalog_page.songs.clone(),
     };
+    let is_searching = search_results.is_some();
     
     html! {
         <div class="w3-container" id="songs-list">   
@@ -199,12 +356,30 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
                     type="text"
                     placeholder="Rechercher une chanson..."
                     value={(*search_query).clone()}
-                    oninput={Callback::from(move |e: InputEvent| {
-                        let input = e.target_unchecked_into::<web_sys::HtmlInputElement>();
-                        search_query.set(input.value());
-                    })}
+                    oninput={on_search_input}
                 />
+                if sources.len() > 1 {
+                    <select onchange={on_source_change}>
+                        <option value="" selected={source_filter.is_none()}>{ "Toutes les listes" }</option>
+                        {
+                            for sources.iter().map(|source| html! {
+                                <option value={source.clone()} selected={source_filter.as_deref() == Some(source.as_str())}>{ source }</option>
+                            })
+                        }
+                    </select>
+                }
             </div>
+            <FilterChips filters={catalog_page.filters.clone()} selected={(*chips).clone()} on_change={on_chips_change} />
+            if !is_searching {
+                <div class="letter-index">
+                    <button class={classes!("btn", letter.is_none().then_some("selected"))} onclick={on_letter(None)}>{ "Tout" }</button>
+                    {
+                        for LETTERS.chars().map(|c| html! {
+                            <button class={classes!("btn", (*letter == Some(c)).then_some("selected"))} onclick={on_letter(Some(c))}>{ c }</button>
+                        })
+                    }
+                </div>
+            }
 
             // Table
             <table class="w3-table w3-striped w3-white">
@@ -213,6 +388,7 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
                     <th onclick={
                         let sort_order = sort_order.clone();
                         let sort_column = sort_column.clone();
+                        let page = page.clone();
 
                         Callback::from(move |_| {
                             // Toggle sort by artist
@@ -220,6 +396,7 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
                             let sort_order = sort_order.clone();
                             sort_column.set("artist".to_string());
                             sort_order.set(new_order);
+                            page.set(1);
                     })}>
                         { "Artiste" }
                         { if *sort_column == "artist" { if *sort_order { "↑" } else { "↓" } } else { "" } }
@@ -227,17 +404,19 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
                     <th onclick={            
                         let sort_order = sort_order.clone();
                         let sort_column = sort_column.clone();
+                        let page = page.clone();
                         Callback::from(move |_| {
                             // Toggle sort by title
                             let new_order = if *sort_column == "title" && *sort_order { false } else { true };
                             sort_column.set("title".to_string());
                             let sort_order = sort_order.clone();
                             sort_order.set(new_order);
+                            page.set(1);
                     })}>
                         { "Titre" }
                         { if *sort_column == "title" { if *sort_order { "↑" } else { "↓" } } else { "" } }
                     </th>
-                        //<th>{ "Paroles" }</th>
+                        <th>{ "Paroles" }</th>
                         {
                             if *karaoke_open {
                                 html! {
@@ -262,16 +441,26 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
                                     on_click.emit(song.clone());
                                 })
                             };
+                            let on_show_lyrics = {
+                                let lyrics_song_id = lyrics_song_id.clone();
+                                let id = song.id;
+                                Callback::from(move |_: MouseEvent| lyrics_song_id.set(Some(id)))
+                            };
 
                             html! {
                                 <tr key={song.id.to_string()}>
                                     <td>{ &song.artist }</td>
-                                    <td>{ &song.title }</td>
-                                    //<td>
-                                    //    <a target="_blank" href={song.lyrics_url.clone()}>
-                                    //        { "Paroles" }
-                                    //    </a>
-                                    //</td>
+                                    <td>
+                                        { &song.title }
+                                        if !song.details().is_empty() {
+                                            <div class="song-details">{ song.details() }</div>
+                                        }
+                                    </td>
+                                    <td>
+                                        <button class="btn" onclick={on_show_lyrics}>
+                                            { "Paroles" }
+                                        </button>
+                                    </td>
                                         {
                                             if *karaoke_open {
                                                 html! {
@@ -293,6 +482,13 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
                     }
                 </tbody>
             </table>
+            if !is_searching && catalog_page.page_count() > 1 {
+                <div class="pagination">
+                    <button class="btn" disabled={*page <= 1} onclick={go_to_page(page.saturating_sub(1))}>{ "‹ Précédent" }</button>
+                    <span>{ format!("Page {} / {} ({} chansons)", *page, catalog_page.page_count(), catalog_page.total) }</span>
+                    <button class="btn" disabled={*page >= catalog_page.page_count()} onclick={go_to_page(*page + 1)}>{ "Suivant ›" }</button>
+                </div>
+            }
             {
                 if let Some(_) = &*selected_song_to_add {
                     html! {
@@ -306,8 +502,17 @@ pub fn songs_list(SongListProps { on_add, karaoke_open, jukebox }: &SongListProp
                     html! {}
                 }
             }
+            if let Some(song_id) = *lyrics_song_id {
+                <LyricsViewer song_id={Some(song_id)} on_close={hide_lyrics} />
+            }
+            if let Some(message) = &*add_error_message {
+                <PopupConfirm message={message.clone()} on_close={hide_add_error} />
+            }
+            if let Some(diff) = &*update_report {
+                <CatalogDiffReport diff={diff.clone()} on_close={hide_update_report} />
+            }
             
-            if is_admin_page {
+            if is_admin {
                 <button onclick={admin_refresh_song} class="admin-button">
                     { "Actualiser la liste de chanson depuis le Google Drive" }
                 </button>
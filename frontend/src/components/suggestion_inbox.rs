@@ -0,0 +1,261 @@
+use yew::prelude::*;
+use gloo_net::http::Request;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::auth;
+use crate::components::catalog_refresh_status::format_date;
+use crate::components::popup_promote_suggestion::PopupPromoteSuggestion;
+use crate::components::popup_confirm::PopupConfirm;
+use crate::types::suggestion::{PromoteSuggestion, Suggestion, SuggestionInbox as Inbox};
+
+
+async fn get_inbox(status: Option<String>) -> Option<Inbox> {
+    let config: Config = Config::load();
+    let url = match status {
+        Some(status) => format!("{}/suggestions?status={}", config.backoffice_url, status),
+        None => format!("{}/suggestions", config.backoffice_url),
+    };
+
+    match auth::with_auth(Request::get(&url)).send().await {
+        Ok(response) if response.ok() => response.json::<Inbox>().await.ok(),
+        Ok(response) => {
+            web_sys::console::error_1(&format!("suggestions error: {}", response.status()).into());
+            None
+        }
+        Err(err) => {
+            web_sys::console::error_1(&format!("Request send error: {}", err).into());
+            None
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    content: String,
+}
+
+// POST to an admin suggestion endpoint, the message of the server when it fails
+async fn post_suggestion(endpoint: &str, body: serde_json::Value) -> Result<(), String> {
+    let config: Config = Config::load();
+    let url = format!("{}/{}", config.backoffice_url, endpoint);
+
+    match auth::with_auth(Request::post(&url))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+    {
+        Ok(request) => match request.send().await {
+            Ok(resp) if resp.ok() => Ok(()),
+            Ok(resp) => {
+                web_sys::console::error_1(&format!("{} error: {}", endpoint, resp.status()).into());
+                match resp.json::<ErrorResponse>().await {
+                    Ok(error) => Err(error.content),
+                    Err(_) => Err(format!("Erreur {}", resp.status())),
+                }
+            }
+            Err(err) => {
+                web_sys::console::error_1(&format!("Network error: {}", err).into());
+                Err("Le serveur ne répond pas.".to_string())
+            }
+        },
+        Err(err) => {
+            web_sys::console::error_1(&format!("Failed to create request: {}", err).into());
+            Err(err.to_string())
+        }
+    }
+}
+
+
+#[derive(Properties, PartialEq)]
+pub struct SuggestionInboxProps {
+    #[prop_or_default]
+    pub version: u64, // Changed when the suggestions must be fetched again
+}
+
+/// Suggestions of the guests for the maestro, most voted then newest first
+#[function_component(SuggestionInbox)]
+pub fn suggestion_inbox(props: &SuggestionInboxProps) -> Html {
+    let inbox = use_state(Inbox::default);
+    let filter = use_state(|| Some("new".to_string())); // None shows every suggestion
+    let to_promote = use_state(|| None::<Suggestion>);
+    let promote_error = use_state(|| None::<String>);
+    let message = use_state(|| None::<String>);
+
+    let load = {
+        let inbox = inbox.clone();
+        let filter = filter.clone();
+        Callback::from(move |_: ()| {
+            let inbox = inbox.clone();
+            let status = (*filter).clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Some(loaded) = get_inbox(status).await {
+                    inbox.set(loaded);
+                }
+            });
+        })
+    };
+
+    {
+        let load = load.clone();
+        use_effect_with((props.version, (*filter).clone()), move |_| {
+            load.emit(());
+            || ()
+        });
+    }
+
+    let on_filter = |status: Option<&'static str>| {
+        let filter = filter.clone();
+        Callback::from(move |_: MouseEvent| filter.set(status.map(str::to_string)))
+    };
+
+    let on_status = |suggestion: &Suggestion, status: &'static str| {
+        let id = suggestion.id;
+        let load = load.clone();
+        Callback::from(move |_: MouseEvent| {
+            let load = load.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if post_suggestion("change-suggestion-status", serde_json::json!({ "id": id, "status": status })).await.is_ok() {
+                    load.emit(());
+                }
+            });
+        })
+    };
+
+    let on_delete = |suggestion: &Suggestion| {
+        let suggestion = suggestion.clone();
+        let load = load.clone();
+        Callback::from(move |_: MouseEvent| {
+            let load = load.clone();
+            let suggestion = suggestion.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if post_suggestion("delete-suggestion", serde_json::json!(suggestion)).await.is_ok() {
+                    load.emit(());
+                }
+            });
+        })
+    };
+
+    let on_show_promote = |suggestion: &Suggestion| {
+        let suggestion = suggestion.clone();
+        let to_promote = to_promote.clone();
+        let promote_error = promote_error.clone();
+        Callback::from(move |_: MouseEvent| {
+            promote_error.set(None);
+            to_promote.set(Some(suggestion.clone()));
+        })
+    };
+
+    let hide_promote = {
+        let to_promote = to_promote.clone();
+        Callback::from(move |_| to_promote.set(None))
+    };
+
+    let on_promote = {
+        let to_promote = to_promote.clone();
+        let promote_error = promote_error.clone();
+        let message = message.clone();
+        let load = load.clone();
+        Callback::from(move |request: PromoteSuggestion| {
+            let to_promote = to_promote.clone();
+            let promote_error = promote_error.clone();
+            let message = message.clone();
+            let load = load.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let song = format!("{} - {}", request.artist.trim(), request.title.trim());
+                match post_suggestion("promote-suggestion", serde_json::json!(request)).await {
+                    Ok(()) => {
+                        to_promote.set(None);
+                        message.set(Some(format!("« {} » a été ajoutée au catalogue.", song)));
+                        load.emit(());
+                    }
+                    Err(error) => promote_error.set(Some(error)),
+                }
+            });
+        })
+    };
+
+    let hide_message = {
+        let message = message.clone();
+        Callback::from(move |_| message.set(None))
+    };
+
+    let filter_button = |label: String, status: Option<&'static str>| html! {
+        <button class={classes!("btn", (filter.as_deref() == status).then_some("selected"))} onclick={on_filter(status)}>
+            { label }
+        </button>
+    };
+
+    html! {
+        <div id="suggestion-inbox">
+            <h3>{ format!("Suggestions ({} non lues)", inbox.unread) }</h3>
+            <div class="filter-chips">
+                { filter_button(format!("Non lues ({})", inbox.unread), Some("new")) }
+                { filter_button(format!("Traitées ({})", inbox.handled), Some("handled")) }
+                { filter_button(format!("Refusées ({})", inbox.rejected), Some("rejected")) }
+                { filter_button(format!("Ajoutées ({})", inbox.fulfilled), Some("fulfilled")) }
+                { filter_button("Toutes".to_string(), None) }
+            </div>
+            if inbox.suggestions.is_empty() {
+                <p>{ "Aucune suggestion." }</p>
+            } else {
+                <table class="w3-table w3-striped w3-white">
+                    <thead>
+                        <tr>
+                            <th>{ "Date" }</th>
+                            <th>{ "Suggestion" }</th>
+                            <th>{ "Votes" }</th>
+                            <th>{ "Statut" }</th>
+                            <th>{ "Actions" }</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {
+                            for inbox.suggestions.iter().map(|suggestion| html! {
+                                <tr key={suggestion.id.to_string()} class={classes!((suggestion.status.as_deref() == Some("new")).then_some("unread"))}>
+                                    <td>{ format_date(&suggestion.created_at) }</td>
+                                    <td class="suggestion-content">
+                                        if let Some(song) = suggestion.song() {
+                                            <strong>{ song }</strong>
+                                            if !suggestion.content.is_empty() {
+                                                <br />
+                                            }
+                                        }
+                                        { &suggestion.content }
+                                    </td>
+                                    <td>{ suggestion.votes }</td>
+                                    <td>{ suggestion.status_label() }</td>
+                                    <td>
+                                        if suggestion.status.as_deref() != Some("fulfilled") {
+                                            <button class="btn" onclick={on_show_promote(suggestion)}>{ "Ajouter au catalogue" }</button>
+                                        }
+                                        if suggestion.status.as_deref() != Some("handled") {
+                                            <button class="btn" onclick={on_status(suggestion, "handled")}>{ "Traitée" }</button>
+                                        }
+                                        if suggestion.status.as_deref() != Some("rejected") {
+                                            <button class="btn" onclick={on_status(suggestion, "rejected")}>{ "Refuser" }</button>
+                                        }
+                                        if suggestion.status.as_deref() != Some("new") {
+                                            <button class="btn" onclick={on_status(suggestion, "new")}>{ "Non lue" }</button>
+                                        }
+                                        <button class="btn" onclick={on_delete(suggestion)}>{ "Supprimer" }</button>
+                                    </td>
+                                </tr>
+                            })
+                        }
+                    </tbody>
+                </table>
+            }
+            if let Some(suggestion) = &*to_promote {
+                <PopupPromoteSuggestion
+                    suggestion={suggestion.clone()}
+                    on_validate={on_promote}
+                    on_cancel={hide_promote}
+                    error={(*promote_error).clone()}
+                />
+            }
+            if let Some(message) = &*message {
+                <PopupConfirm message={message.clone()} on_close={hide_message} />
+            }
+        </div>
+    }
+}
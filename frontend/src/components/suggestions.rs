@@ -1,48 +1,168 @@
 use yew::prelude::*;
 use crate::config::Config;
+use gloo::storage::{LocalStorage, Storage};
 use gloo_net::http::Request;
-
 use serde::Deserialize;
-use serde::Serialize;
-use yew::Properties;
 
+use crate::client;
 use crate::components::popup_confirm::PopupConfirm;
+use crate::types::suggestion::{RequestedSong, Suggestion};
+
+
+// Suggestions this browser already voted for, one vote each
+const VOTES_KEY: &str = "karaoke_suggestion_votes";
+const FAILURE_MESSAGE: &str = "Echec de l'envoi de suggestion :'(";
 
 
-#[derive(Clone, PartialEq, Serialize, Deserialize, Properties)]
+// Refused requests come with a message for the guest
+#[derive(Deserialize)]
+struct ErrorResponse {
+    content: String,
+}
+
+
+async fn get_requested_songs() -> Option<Vec<RequestedSong>> {
+    let config: Config = Config::load();
+    let url = format!("{}/requested-songs", config.backoffice_url);
+
+    match Request::get(&url).send().await {
+        Ok(response) if response.ok() => response.json::<Vec<RequestedSong>>().await.ok(),
+        Ok(response) => {
+            web_sys::console::error_1(&format!("requested-songs error: {}", response.status()).into());
+            None
+        }
+        Err(err) => {
+            web_sys::console::error_1(&format!("Request send error: {}", err).into());
+            None
+        }
+    }
+}
+
+async fn vote_for(id: i32) -> Result<(), String> {
+    let config: Config = Config::load();
+    let url = format!("{}/vote-suggestion", config.backoffice_url);
 
-pub struct Suggestion {
-    pub id: i32,
-    pub content: String,
+    match client::with_client_id(Request::post(&url))
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "id": id }).to_string())
+    {
+        Ok(request) => match request.send().await {
+            Ok(resp) if resp.ok() => Ok(()),
+            Ok(resp) => match resp.json::<ErrorResponse>().await {
+                Ok(error) if resp.status() < 500 => Err(error.content),
+                _ => Err("Impossible de voter, réessayez plus tard.".to_string()),
+            },
+            Err(err) => {
+                web_sys::console::error_1(&format!("Network error: {}", err).into());
+                Err("Impossible de voter, réessayez plus tard.".to_string())
+            }
+        },
+        Err(err) => {
+            web_sys::console::error_1(&format!("Failed to create request: {}", err).into());
+            Err("Impossible de voter, réessayez plus tard.".to_string())
+        }
+    }
+}
 
+fn voted_suggestions() -> Vec<i32> {
+    LocalStorage::get(VOTES_KEY).unwrap_or_default()
 }
 
 
+#[derive(Properties, PartialEq)]
+pub struct SuggestionsProps {
+    #[prop_or_default]
+    pub version: u64, // Changed when the requested songs must be fetched again
+}
+
+/// Song suggestions of the guests, and the songs already asked that they can vote for
 #[function_component(Suggestions)]
-pub fn suggestions() -> Html {
+pub fn suggestions(props: &SuggestionsProps) -> Html {
+    let artist: UseStateHandle<String> = use_state(String::new);
+    let title: UseStateHandle<String> = use_state(String::new);
     let input_value: UseStateHandle<String> = use_state(|| "".to_string()); // State to hold the input text
+    let requested_songs: UseStateHandle<Vec<RequestedSong>> = use_state(Vec::new);
+    let voted: UseStateHandle<Vec<i32>> = use_state(voted_suggestions);
+    let vote_error: UseStateHandle<Option<String>> = use_state(|| None);
     let answer: UseStateHandle<String> = use_state(|| "".to_string()); // State to hold the answer
     let show_popup = use_state(|| false);
     let message = use_state(|| "Votre suggestion a été enregistrée.".to_string());
 
 
+    let load_requested_songs = {
+        let requested_songs = requested_songs.clone();
+        Callback::from(move |_: ()| {
+            let requested_songs = requested_songs.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Some(songs) = get_requested_songs().await {
+                    requested_songs.set(songs);
+                }
+            });
+        })
+    };
+
+    {
+        let load_requested_songs = load_requested_songs.clone();
+        use_effect_with(props.version, move |_| {
+            load_requested_songs.emit(());
+            || ()
+        });
+    }
+
     let on_close = {
         let show_popup = show_popup.clone();
+        let artist = artist.clone();
+        let title = title.clone();
         let input_value = input_value.clone();
         Callback::from(move |_| {
             show_popup.set(false);
+            artist.set("".to_string());
+            title.set("".to_string());
             input_value.set("".to_string());
         })
     };
 
+    let on_vote = |song: &RequestedSong| {
+        let id = song.id;
+        let voted = voted.clone();
+        let vote_error = vote_error.clone();
+        let load_requested_songs = load_requested_songs.clone();
+        Callback::from(move |_: MouseEvent| {
+            let voted = voted.clone();
+            let vote_error = vote_error.clone();
+            let load_requested_songs = load_requested_songs.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match vote_for(id).await {
+                    Ok(()) => {
+                        let mut ids = voted_suggestions();
+                        ids.push(id);
+                        if let Err(err) = LocalStorage::set(VOTES_KEY, &ids) {
+                            web_sys::console::error_1(&format!("Failed to store votes: {}", err).into());
+                        }
+                        voted.set(ids);
+                        vote_error.set(None);
+                    }
+                    Err(error) => vote_error.set(Some(error)),
+                }
+                load_requested_songs.emit(());
+            });
+        })
+    };
+
 
     let on_validate: Callback<MouseEvent> = {
+        let artist = artist.clone();
+        let title = title.clone();
         let input_value = input_value.clone();
+        let load_requested_songs = load_requested_songs.clone();
         let answer = answer.clone();
         let show_popup = show_popup.clone();
         let message = message.clone();
         Callback::from(move |_| {
+            let artist = artist.clone();
+            let title = title.clone();
             let input_value = input_value.clone();
+            let load_requested_songs = load_requested_songs.clone();
             let answer = answer.clone();
             let message = message.clone();
             let show_popup = show_popup.clone();
@@ -51,9 +171,15 @@ pub fn suggestions() -> Html {
                 let url = format!("{}/add-suggestion", config.backoffice_url);
                 let suggestion = Suggestion {
                     id :0,
-                    content : (*input_value).clone()
+                    content : (*input_value).clone(),
+                    artist: (*artist).clone(),
+                    title: (*title).clone(),
+                    votes: 0,
+                    status: None,
+                    created_at: None,
+                    proposed_song: None,
                 };
-                match Request::post(&url)
+                match client::with_client_id(Request::post(&url))
                     .header("Content-Type", "application/json")
                     .body(serde_json::to_string(&suggestion).unwrap())
                 {
@@ -63,10 +189,15 @@ pub fn suggestions() -> Html {
                                 web_sys::console::log_1(&"Suggestions successfully sent!".into());
                                 answer.set("Votre suggestion a été enregistrée !".to_string());
                                 message.set("Votre suggestion a été enregistrée !".to_string());     
+                                load_requested_songs.emit(());
                             } else {
                                 web_sys::console::error_1(&format!("Failed to send Suggestions: {:?}", resp).into());
-                                answer.set("Echec de l'envoi de suggestion :'(".to_string());
-                                message.set("Echec de l'envoi de suggestion :'(".to_string());
+                                let failure = match resp.json::<ErrorResponse>().await {
+                                    Ok(error) if resp.status() < 500 => error.content,
+                                    _ => FAILURE_MESSAGE.to_string(),
+                                };
+                                answer.set(failure.clone());
+                                message.set(failure);
                             }
                             show_popup.set(true);
                         }
@@ -90,6 +221,13 @@ pub fn suggestions() -> Html {
         })
     };
 
+    let on_field_input = |field: UseStateHandle<String>| {
+        Callback::from(move |e: InputEvent| {
+            let input = e.target_unchecked_into::<web_sys::HtmlInputElement>();
+            field.set(input.value());
+        })
+    };
+
     let on_input = {
         let input_value = input_value.clone();
         Callback::from(move |e: InputEvent| {
@@ -102,14 +240,44 @@ pub fn suggestions() -> Html {
     html! {
         <div id="suggestion">
             <h1>{ "Des suggestions ?" }</h1>
+            <p>
+                <label>{ "Artiste : " }</label>
+                <input type="text" maxlength="150" value={(*artist).clone()} oninput={on_field_input(artist.clone())} />
+            </p>
+            <p>
+                <label>{ "Titre : " }</label>
+                <input type="text" maxlength="150" value={(*title).clone()} oninput={on_field_input(title.clone())} />
+            </p>
+            <label>{ "Un mot pour le maestro (facultatif) :" }</label>
             <textarea
                 value={(*input_value).clone()}
                 oninput={on_input}
                 maxwidth="2000"
+                maxlength="1000"
             />
-            <button onclick={on_validate}>{ "Valider" }</button>
+            <button onclick={on_validate} disabled={title.trim().is_empty() && input_value.trim().is_empty()}>{ "Valider" }</button>
             <p>{ (*answer).clone() }</p>
 
+            if !requested_songs.is_empty() {
+                <h2>{ "Les chansons demandées" }</h2>
+                <ul class="requested-songs">
+                    {
+                        for requested_songs.iter().map(|song| html! {
+                            <li key={song.id.to_string()}>
+                                <span class="requested-song">
+                                    { if song.artist.is_empty() { song.title.clone() } else { format!("{} - {}", song.artist, song.title) } }
+                                </span>
+                                <span class="requested-votes">{ format!("{} vote(s)", song.votes) }</span>
+                                <button class="btn" onclick={on_vote(song)} disabled={voted.contains(&song.id)}>{ "+1" }</button>
+                            </li>
+                        })
+                    }
+                </ul>
+                if let Some(error) = &*vote_error {
+                    <p class="w3-text-red">{ error }</p>
+                }
+            }
+
             if *show_popup {
                 <PopupConfirm message={(*message).clone()} on_close={on_close.clone()} />
             }
@@ -0,0 +1,175 @@
+use gloo::timers::callback::Interval;
+use yew::prelude::*;
+
+use crate::components::chosen_songs_list::refresh_chosen_songs;
+use crate::components::config_toggle_button::get_boolean_config;
+use crate::components::content::{get_content, SafeHtml};
+use crate::components::join_qr_code::{get_join_url, JoinQrCode};
+use crate::live::{use_live_events, LiveEvent};
+use crate::types::song::Song;
+
+
+// Queued songs shown after the one being performed
+const NEXT_SONGS: usize = 5;
+// Refresh delays, the queue is only polled while the live updates are down
+const QUEUE_POLLING_MS: u32 = 15_000;
+const CONTENT_POLLING_MS: u32 = 300_000;
+const QR_CODE_SIZE: u32 = 400;
+
+
+/// Display for the venue screen on "/tv" : the song being performed, the next ones,
+/// the intro text and a QR code to join. Nothing to click, it follows the live updates.
+#[function_component(TvMode)]
+pub fn tv_mode() -> Html {
+    let songs: UseStateHandle<Vec<Song>> = use_state(Vec::new);
+    let intro: UseStateHandle<Option<String>> = use_state(|| None);
+    let is_jukebox = use_state(|| false);
+    let join_url: UseStateHandle<String> = use_state(String::new);
+    let qr_code_version: UseStateHandle<u64> = use_state(|| 0);
+
+    let load_intro = {
+        let intro = intro.clone();
+        Callback::from(move |_: ()| {
+            let intro = intro.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Some(content) = get_content("text_intro".to_string()).await {
+                    intro.set(Some(content.content_text));
+                }
+            });
+        })
+    };
+
+    let load_jukebox = {
+        let is_jukebox = is_jukebox.clone();
+        Callback::from(move |_: ()| {
+            let is_jukebox = is_jukebox.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                is_jukebox.set(get_boolean_config("jukebox".to_string()).await);
+            });
+        })
+    };
+
+    let load_join_url = {
+        let join_url = join_url.clone();
+        let qr_code_version = qr_code_version.clone();
+        Callback::from(move |_: ()| {
+            let join_url = join_url.clone();
+            qr_code_version.set(js_sys::Date::now() as u64);
+            wasm_bindgen_futures::spawn_local(async move {
+                join_url.set(get_join_url().await);
+            });
+        })
+    };
+
+    let on_live_event = {
+        let songs = songs.clone();
+        let is_jukebox = is_jukebox.clone();
+        let load_intro = load_intro.clone();
+        let load_jukebox = load_jukebox.clone();
+        let load_join_url = load_join_url.clone();
+        Callback::from(move |event: LiveEvent| match event {
+            LiveEvent::Playlist => refresh_chosen_songs(songs.clone()),
+            LiveEvent::Config { name, value } => match name.as_str() {
+                "jukebox" => is_jukebox.set(value == "yes"),
+                "queue_order" => refresh_chosen_songs(songs.clone()),
+                "public_url" => load_join_url.emit(()),
+                _ => (),
+            },
+            LiveEvent::Resync => {
+                refresh_chosen_songs(songs.clone());
+                load_intro.emit(());
+                load_jukebox.emit(());
+                load_join_url.emit(());
+            }
+            LiveEvent::Suggestions => (),
+        })
+    };
+    let is_live = use_live_events(on_live_event);
+
+    {
+        let songs = songs.clone();
+        use_effect_with((), move |_| {
+            refresh_chosen_songs(songs);
+            load_intro.emit(());
+            load_jukebox.emit(());
+            load_join_url.emit(());
+
+            // The intro text has no live update
+            let interval = Interval::new(CONTENT_POLLING_MS, move || load_intro.emit(()));
+            move || { drop(interval); }
+        });
+    }
+
+    {
+        // Polling fallback while the live updates are disconnected
+        let songs = songs.clone();
+        use_effect_with(is_live, move |live| {
+            let interval = (!*live).then(|| {
+                Interval::new(QUEUE_POLLING_MS, move || refresh_chosen_songs(songs.clone()))
+            });
+            move || { drop(interval); }
+        });
+    }
+
+    let now_playing = songs.iter().find(|song| song.is_playing());
+    let next_songs: Vec<&Song> = songs.iter().filter(|song| !song.is_playing()).take(NEXT_SONGS).collect();
+
+    html! {
+        <div class="tv-mode">
+            <div class="tv-now-playing">
+                {
+                    match now_playing {
+                        Some(song) => html! {
+                            <>
+                                <div class="tv-label">{ "En ce moment" }</div>
+                                <div class="tv-title">{ &song.title }</div>
+                                <div class="tv-artist">{ &song.artist }</div>
+                                if !*is_jukebox {
+                                    if let Some(singer) = &song.singer {
+                                        <div class="tv-singer"><i class="fa fa-microphone"></i>{ " " }{ singer }</div>
+                                    }
+                                }
+                            </>
+                        },
+                        None => html! {
+                            <div class="tv-title">{ "Carpe Dièse" }</div>
+                        },
+                    }
+                }
+            </div>
+            <div class="tv-row">
+                <div class="tv-next">
+                    <div class="tv-label">{ "À suivre" }</div>
+                    if next_songs.is_empty() {
+                        <p>{ "Choisissez votre chanson !" }</p>
+                    } else {
+                        <ol>
+                            {
+                                for next_songs.iter().map(|song| html! {
+                                    <li key={song.id.to_string()}>
+                                        <span class="tv-next-song">{ format!("{} - {}", song.artist, song.title) }</span>
+                                        if !*is_jukebox {
+                                            if let Some(singer) = &song.singer {
+                                                <span class="tv-next-singer">{ singer }</span>
+                                            }
+                                        }
+                                    </li>
+                                })
+                            }
+                        </ol>
+                    }
+                </div>
+                <div class="tv-join">
+                    <JoinQrCode size={QR_CODE_SIZE} version={*qr_code_version} />
+                    <div>{ "Choisissez votre chanson sur" }</div>
+                    <div class="tv-join-url">{ &*join_url }</div>
+                </div>
+            </div>
+            if let Some(intro) = &*intro {
+                <div class="tv-intro">
+                    <SafeHtml html={intro.clone()} />
+                </div>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,96 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use futures::StreamExt;
+use gloo::timers::future::TimeoutFuture;
+use gloo_net::eventsource::futures::EventSource;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+use crate::config::Config;
+
+// Reconnection delay, doubled after each failure
+const MIN_RETRY_MS: u32 = 1_000;
+const MAX_RETRY_MS: u32 = 30_000;
+
+/// Change pushed by the backend on `/events`
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LiveEvent {
+    Playlist,
+    Config { name: String, value: String },
+    Suggestions,
+    Resync,
+}
+
+/// Subscribe to the backend live events, reconnecting when the stream drops.
+/// Returns whether the stream is currently connected so callers can fall back to polling.
+#[hook]
+pub fn use_live_events(on_event: Callback<LiveEvent>) -> bool {
+    let connected = use_state(|| false);
+    // The latest callback, the stream is opened once but the caller's props and state change
+    let on_event_ref = use_mut_ref(|| on_event.clone());
+    *on_event_ref.borrow_mut() = on_event;
+
+    {
+        let connected = connected.clone();
+        use_effect_with((), move |_| {
+            let stopped = Rc::new(Cell::new(false));
+            let stopped_task = stopped.clone();
+            // Open source, closed on unmount to end the stream even when no message comes
+            let source: Rc<RefCell<Option<EventSource>>> = Rc::new(RefCell::new(None));
+            let source_task = source.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let config = Config::load();
+                let url = format!("{}/events", config.backoffice_url);
+                let mut retry_ms = MIN_RETRY_MS;
+
+                while !stopped_task.get() {
+                    match EventSource::new(&url) {
+                        Ok(mut event_source) => match event_source.subscribe("message") {
+                            Ok(mut messages) => {
+                                *source_task.borrow_mut() = Some(event_source);
+                                while let Some(Ok((_, message))) = messages.next().await {
+                                    if stopped_task.get() {
+                                        break;
+                                    }
+                                    let data = message.data().as_string().unwrap_or_default();
+                                    match serde_json::from_str::<LiveEvent>(&data) {
+                                        Ok(event) => {
+                                            connected.set(true);
+                                            retry_ms = MIN_RETRY_MS;
+                                            let on_event = on_event_ref.borrow().clone();
+                                            on_event.emit(event);
+                                        }
+                                        Err(err) => web_sys::console::error_1(&format!("live event parse error: {}", err).into()),
+                                    }
+                                }
+                                if let Some(event_source) = source_task.borrow_mut().take() {
+                                    event_source.close();
+                                }
+                            }
+                            Err(err) => web_sys::console::error_1(&format!("live event subscribe error: {}", err).into()),
+                        },
+                        Err(err) => web_sys::console::error_1(&format!("live event connection error: {}", err).into()),
+                    }
+
+                    if stopped_task.get() {
+                        break;
+                    }
+                    connected.set(false);
+                    web_sys::console::log_1(&format!("Live events lost, retrying in {} ms", retry_ms).into());
+                    TimeoutFuture::new(retry_ms).await;
+                    retry_ms = (retry_ms * 2).min(MAX_RETRY_MS);
+                }
+            });
+
+            move || {
+                stopped.set(true);
+                if let Some(event_source) = source.borrow_mut().take() {
+                    event_source.close();
+                }
+            }
+        });
+    }
+
+    *connected
+}
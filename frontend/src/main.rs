@@ -11,14 +11,35 @@ mod components {
     pub mod popup_confirm;
     pub mod config_toggle_button;
     pub mod config_text_input;
+    pub mod login_form;
+    pub mod config_select;
+    pub mod sheet_layout_form;
+    pub mod catalog_upload;
+    pub mod catalog_diff_report;
+    pub mod catalog_refresh_status;
+    pub mod filter_chips;
+    pub mod lyrics_viewer;
+    pub mod tv_mode;
+    pub mod join_qr_code;
+    pub mod suggestion_inbox;
+    pub mod popup_promote_suggestion;
 }
 
 mod types {
     pub mod song;
     pub mod bo_config;
+    pub mod sheet_layout;
+    pub mod catalog_diff;
+    pub mod refresh_status;
+    pub mod catalog_page;
+    pub mod lyrics;
+    pub mod suggestion;
 }
 
 mod config;
+mod auth;
+mod client;
+mod live;
 
 use crate::components::songs_list::SongsList;
 use crate::components::chosen_songs_list::ChosenSongsList;
@@ -26,7 +47,18 @@ use crate::components::suggestions::Suggestions;
 use crate::components::content::ContentComponent;
 use crate::components::config_toggle_button::get_boolean_config;
 use crate::components::config_toggle_button::ConfigToggleButton;
-use crate::components::config_text_input::ConfigTextInput;
+use crate::components::config_text_input::{get_text_config, ConfigTextInput};
+use crate::components::login_form::LoginForm;
+use crate::components::config_select::ConfigSelect;
+use crate::components::sheet_layout_form::SheetLayoutForm;
+use crate::components::catalog_upload::CatalogUpload;
+use crate::components::catalog_refresh_status::CatalogRefreshStatus;
+use crate::components::tv_mode::TvMode;
+use crate::components::join_qr_code::{JoinQrCode, qr_code_url};
+use crate::components::suggestion_inbox::SuggestionInbox;
+use crate::config::Config;
+use gloo_net::http::Request;
+use crate::live::{use_live_events, LiveEvent};
 
 
 
@@ -38,6 +70,9 @@ fn app() -> Html {
     let is_karaoke_open: UseStateHandle<bool> = use_state(|| false);
 
     let is_jukebox: UseStateHandle<bool> = use_state(|| false);
+    let is_round_robin: UseStateHandle<bool> = use_state(|| false); // The queue can't be reordered by hand
+    let qr_code_version: UseStateHandle<u64> = use_state(|| 0); // Changed to reload the QR code image
+    let suggestions_version: UseStateHandle<u64> = use_state(|| 0); // Changed to reload the suggestion inbox and the requested songs
 
     let trigger_refresh = {
         let refresh_chosen_songs = refresh_chosen_songs.clone();
@@ -45,13 +80,69 @@ fn app() -> Html {
     };
     let is_karaoke_open_clone = is_karaoke_open.clone();
     let is_jukebox_clone = is_jukebox.clone();
+
+    let on_live_event = {
+        let refresh_chosen_songs = refresh_chosen_songs.clone();
+        let is_karaoke_open = is_karaoke_open.clone();
+        let is_jukebox = is_jukebox.clone();
+        let is_round_robin = is_round_robin.clone();
+        let qr_code_version = qr_code_version.clone();
+        let suggestions_version = suggestions_version.clone();
+        Callback::from(move |event: LiveEvent| match event {
+            LiveEvent::Playlist => refresh_chosen_songs.set(true),
+            LiveEvent::Config { name, value } => match name.as_str() {
+                "open" => is_karaoke_open.set(value == "yes"),
+                "jukebox" => is_jukebox.set(value == "yes"),
+                "queue_order" => {
+                    is_round_robin.set(value == "round_robin");
+                    refresh_chosen_songs.set(true);
+                }
+                "public_url" => qr_code_version.set(js_sys::Date::now() as u64),
+                _ => (),
+            },
+            LiveEvent::Resync => {
+                refresh_chosen_songs.set(true);
+                qr_code_version.set(js_sys::Date::now() as u64);
+                suggestions_version.set(js_sys::Date::now() as u64);
+                let is_karaoke_open = is_karaoke_open.clone();
+                let is_jukebox = is_jukebox.clone();
+                let is_round_robin = is_round_robin.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    is_karaoke_open.set(get_boolean_config("open".to_string()).await);
+                    is_jukebox.set(get_boolean_config("jukebox".to_string()).await);
+                    is_round_robin.set(get_text_config("queue_order".to_string()).await == "round_robin");
+                });
+            }
+            LiveEvent::Suggestions => suggestions_version.set(js_sys::Date::now() as u64),
+        })
+    };
+    let is_live = use_live_events(on_live_event);
     let location = window()
     .and_then(|win: web_sys::Window| win.location().pathname().ok()) // Get the path portion of the URL
     .unwrap_or_else(|| "/".to_string()); // Default to "/" if retrieval fails
 
-    // Check if the current URL contains "/admin"
-    let is_admin_page = location.contains("/maestro");
+    // The login form is only offered on "/maestro", admin features need a valid session
+    let is_maestro_page = location.contains("/maestro");
+    let is_admin = auth::is_logged_in();
 
+    use_effect_with((), move |_| {
+        if is_admin {
+            wasm_bindgen_futures::spawn_local(async move {
+                let config = Config::load();
+                let url = format!("{}/auth-check", config.backoffice_url);
+                match auth::with_auth(Request::get(&url)).send().await {
+                    Ok(resp) if resp.status() == 401 => {
+                        web_sys::console::log_1(&"Admin session rejected, logging out".into());
+                        auth::logout();
+                    }
+                    Ok(_) => (),
+                    Err(err) => web_sys::console::error_1(&format!("auth-check error: {}", err).into()),
+                }
+            });
+        }
+    });
+
+    let on_logout = Callback::from(|_: MouseEvent| auth::logout());
 
 
     use_effect_with((), move |_| {
@@ -68,10 +159,21 @@ fn app() -> Html {
             }
         });
     });
+    {
+        let is_round_robin = is_round_robin.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                is_round_robin.set(get_text_config("queue_order".to_string()).await == "round_robin");
+            });
+        });
+    }
 
     html! {
         <div class="w3-main">
-            <h1 style="text-align:center;">{ "Carpe Dièse" }</h1>
+            <div class="site-header">
+                <h1>{ "Carpe Dièse" }</h1>
+                <JoinQrCode size={96} version={*qr_code_version} />
+            </div>
             <div class="w3-row-padding w3-margin-bottom">
                 <div class="w3-half">
                     <a href="#chosen-song" style="text-decoration: none;">
@@ -106,10 +208,10 @@ fn app() -> Html {
               
             </div>
             <div class="w3-container">   
-                { if *is_karaoke_open || is_admin_page
+                { if *is_karaoke_open || is_admin
                      {
                         html! {
-                            <ChosenSongsList refresh_trigger={refresh_chosen_songs.clone()} jukebox={*is_jukebox}/>
+                            <ChosenSongsList refresh_trigger={refresh_chosen_songs.clone()} jukebox={*is_jukebox} round_robin={*is_round_robin} live={is_live}/>
                         }
                     } else {
                         html! {
@@ -132,26 +234,57 @@ fn app() -> Html {
             </div>
              <SongsList on_add={trigger_refresh.clone()} karaoke_open={*is_karaoke_open} jukebox={*is_jukebox}/>
                 
-             <Suggestions />
+             <Suggestions version={*suggestions_version} />
 
 
-             { if is_admin_page
+             { if is_admin
                 {
                     html! {
                         <p style="center">
+                            <SuggestionInbox version={*suggestions_version} />
                             <ul>
                                 <li>{"Karaoké ouvert :"} <ConfigToggleButton name="open"/></li>
                                 <li>{"Mode Jukebox :"} <ConfigToggleButton name="jukebox"/></li>
-                                <li>{"Id google :"} <ConfigTextInput name="google_sheet_id"/>
+                                <li>{"Chansons en attente max. par chanteur (0 = illimité) :"} <ConfigTextInput name="max_songs_per_singer"/></li>
+                                <li>{"Refuser une chanson déjà en attente :"} <ConfigToggleButton name="reject_duplicate_songs"/></li>
+                                <li>{"Délai avant de reprendre une chanson (minutes, 0 = aucun) :"} <ConfigTextInput name="song_cooldown_minutes"/></li>
+                                <li>{"Mots interdits dans les noms et les suggestions (séparés par des virgules) :"} <ConfigTextInput name="banned_words"/></li>
+                                <li>{"Ordre de passage :"} <ConfigSelect name="queue_order" options={vec![
+                                    ("fifo".to_string(), "Premier arrivé, premier servi".to_string()),
+                                    ("round_robin".to_string(), "Chacun son tour".to_string()),
+                                ]}/></li>
+                                <li>{"Source des chansons :"} <ConfigSelect name="catalog_source" options={vec![
+                                    ("google_sheet".to_string(), "Google Sheet".to_string()),
+                                    ("local_file".to_string(), "Fichier importé".to_string()),
+                                ]}/></li>
+                                <li>{"Importer un fichier CSV ou JSON (titre, artiste, paroles) :"} <CatalogUpload /></li>
+                                <li>{"Mise à jour automatique de la liste (minutes, 0 = désactivée) :"} <ConfigTextInput name="catalog_refresh_minutes"/>
+                                    <CatalogRefreshStatus />
+                                </li>
+                                <li>{"Id google (plusieurs séparés par des virgules) :"} <ConfigTextInput name="google_sheet_id"/>
                                     <ul>
                                         <li>{"Carpe # id : 1KWhp9nuuA4WrbEk2IssQUBVCPjVT6WX9gjuV9qFo7AI"}</li>
                                         <li>{"Annecat playlist # id :1OReTpbzBUhBRmgryjINbRhbxbYKsnTxJVKvBUPL2Wm0"}</li>
                                     </ul>
                                 </li>
+                                <li><SheetLayoutForm /></li>
+                                <li>{"Adresse publique du site pour le QR code (vide = adresse de ce serveur) :"} <ConfigTextInput name="public_url"/>
+                                    <div>
+                                        <JoinQrCode size={200} version={*qr_code_version} />
+                                    </div>
+                                    <a target="_blank" href={qr_code_url("svg", 1000, *qr_code_version)}>{ "Télécharger en SVG" }</a>
+                                    {" · "}
+                                    <a target="_blank" href={qr_code_url("png", 1000, *qr_code_version)}>{ "Télécharger en PNG" }</a>
+                                </li>
                             </ul>
+                            <button onclick={on_logout} class="admin-button">{ "Se déconnecter" }</button>
                         </p>
 
                     }
+                } else if is_maestro_page {
+                    html! {
+                        <LoginForm />
+                    }
                 } else {
                    html! {
                    }
@@ -162,5 +295,14 @@ fn app() -> Html {
 }
 
 fn main() {
-    yew::Renderer::<App>::new().render();
+    // The venue screen has its own page, without the search bars and the admin features
+    let is_tv_page = window()
+        .and_then(|win| win.location().pathname().ok())
+        .is_some_and(|path| path.starts_with("/tv"));
+
+    if is_tv_page {
+        yew::Renderer::<TvMode>::new().render();
+    } else {
+        yew::Renderer::<App>::new().render();
+    }
 }
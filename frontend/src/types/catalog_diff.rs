@@ -0,0 +1,42 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::types::song::Song;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SkippedRow {
+    pub row: usize,
+    pub reason: String, // "missing_columns" or "duplicate"
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Sheet or tab that could not be read, its songs were kept
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct FailedSource {
+    pub source: String,
+    pub error: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SongChange {
+    pub before: Song,
+    pub after: Song,
+}
+
+/// What a catalog refresh changed, returned by `/song-update`
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct CatalogDiff {
+    pub added: Vec<Song>,
+    pub removed: Vec<Song>,
+    pub changed: Vec<SongChange>,
+    pub skipped: Vec<SkippedRow>,
+    #[serde(default)]
+    pub failed: Vec<FailedSource>,
+}
+
+impl CatalogDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty() && self.skipped.is_empty() && self.failed.is_empty()
+    }
+}
@@ -0,0 +1,81 @@
+use serde::Deserialize;
+
+use crate::types::song::Song;
+
+/// One page of the catalog, returned by `/song-data`
+#[derive(Clone, PartialEq, Deserialize, Default)]
+pub struct CatalogPage {
+    pub songs: Vec<Song>,
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub sources: Vec<String>,
+    #[serde(default)]
+    pub filters: CatalogFilters,
+}
+
+/// Values found in the details of the catalog songs
+#[derive(Clone, PartialEq, Deserialize, Default)]
+pub struct CatalogFilters {
+    pub genres: Vec<String>,
+    pub languages: Vec<String>,
+    pub decades: Vec<i32>,
+    pub difficulties: Vec<String>,
+    pub duets: bool,
+}
+
+/// Filter chips picked by the guest, one at most in each group
+#[derive(Clone, PartialEq, Default)]
+pub struct ChipSelection {
+    pub genre: Option<String>,
+    pub language: Option<String>,
+    pub decade: Option<i32>,
+    pub duet: bool,
+    pub difficulty: Option<String>,
+}
+
+impl ChipSelection {
+    /// Query parameters of `/song-data`
+    pub fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = vec![];
+        query.extend(self.genre.clone().map(|genre| ("genre", genre)));
+        query.extend(self.language.clone().map(|language| ("language", language)));
+        query.extend(self.decade.map(|decade| ("decade", decade.to_string())));
+        if self.duet {
+            query.push(("duet", "true".to_string()));
+        }
+        query.extend(self.difficulty.clone().map(|difficulty| ("difficulty", difficulty)));
+        query
+    }
+
+    /// Same filtering as the backend, for the search results
+    pub fn matches(&self, song: &Song) -> bool {
+        let same_text = |selected: &Option<String>, value: &Option<String>| match selected {
+            Some(selected) => value.as_deref().is_some_and(|value| value.to_lowercase() == selected.to_lowercase()),
+            None => true,
+        };
+        same_text(&self.genre, &song.genre)
+            && same_text(&self.language, &song.language)
+            && same_text(&self.difficulty, &song.difficulty)
+            && (self.decade.is_none() || song.decade() == self.decade)
+            && (!self.duet || song.duet)
+    }
+}
+
+/// "Années 80", and "Années 2000" after 1999
+pub fn decade_label(decade: i32) -> String {
+    if (1900..2000).contains(&decade) {
+        format!("Années {}", decade - 1900)
+    } else {
+        format!("Années {}", decade)
+    }
+}
+
+impl CatalogPage {
+    pub fn page_count(&self) -> usize {
+        if self.per_page == 0 {
+            return 1;
+        }
+        self.total.div_ceil(self.per_page).max(1)
+    }
+}
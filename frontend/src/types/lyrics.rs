@@ -0,0 +1,42 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LyricsFormat {
+    Text,
+    Lrc,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct LyricLine {
+    pub time_ms: i64,
+    pub text: String,
+}
+
+/// Lyrics of a song, returned by `/songs/{id}/lyrics` and `/current-lyrics`
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lyrics {
+    pub song_id: i32,
+    pub artist: String,
+    pub title: String,
+    pub format: Option<LyricsFormat>, // None without local lyrics
+    pub content: Option<String>,
+    #[serde(default)]
+    pub lines: Vec<LyricLine>, // Timed lines of LRC lyrics
+    pub url: Option<String>, // External link
+    pub elapsed_ms: Option<i64>, // Only for the song being performed
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct LyricsRequest {
+    pub song_id: i32,
+    pub content: String, // Empty to delete the lyrics
+}
+
+impl Lyrics {
+    /// Index of the LRC line being sung after `position_ms` of the song
+    pub fn current_line(&self, position_ms: i64) -> Option<usize> {
+        self.lines.iter().rposition(|line| line.time_ms <= position_ms)
+    }
+}
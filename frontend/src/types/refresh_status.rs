@@ -0,0 +1,14 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Outcome of the catalog refreshes, dates are RFC 3339 strings
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct RefreshStatus {
+    pub interval_minutes: u64,
+    pub in_progress: bool,
+    pub last_success: Option<String>,
+    pub last_error: Option<String>,
+    pub last_error_message: Option<String>,
+    pub consecutive_failures: u32,
+    pub next_refresh: Option<String>,
+}
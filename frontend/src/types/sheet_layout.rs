@@ -0,0 +1,54 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Columns of the Google Sheet holding the song fields, a header name or a column letter
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SheetLayout {
+    #[serde(default)]
+    pub name: String,
+    pub range: String,
+    #[serde(default)]
+    pub tabs: Vec<String>,
+    pub has_header: bool,
+    pub title_column: String,
+    pub artist_column: String,
+    pub lyrics_url_column: String,
+    #[serde(default)]
+    pub genre_column: String,
+    #[serde(default)]
+    pub language_column: String,
+    #[serde(default)]
+    pub year_column: String,
+    #[serde(default)]
+    pub duet_column: String,
+    #[serde(default)]
+    pub difficulty_column: String,
+    #[serde(default)]
+    pub duration_column: String,
+}
+
+impl Default for SheetLayout {
+    fn default() -> Self {
+        SheetLayout {
+            name: String::new(),
+            range: "A:D".to_string(),
+            tabs: vec![],
+            has_header: true,
+            title_column: "A".to_string(),
+            artist_column: "B".to_string(),
+            lyrics_url_column: "C".to_string(),
+            genre_column: String::new(),
+            language_column: String::new(),
+            year_column: String::new(),
+            duet_column: String::new(),
+            difficulty_column: String::new(),
+            duration_column: String::new(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SheetLayoutRequest {
+    pub sheet_id: String,
+    pub layout: Option<SheetLayout>,
+}
@@ -9,5 +9,49 @@ pub struct Song {
     pub title: String,
     pub lyrics_url: String,
     pub singer: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub song_id: Option<i32>, // Catalog song of a queue entry
+    #[serde(default)]
+    pub source: Option<String>, // Sheet or tab the song comes from
+    // Optional details of a catalog song
+    #[serde(default)]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub year: Option<i32>,
+    #[serde(default)]
+    pub duet: bool,
+    #[serde(default)]
+    pub difficulty: Option<String>,
+    #[serde(default)]
+    pub duration: Option<i32>, // In seconds
+}
+
+impl Song {
+    /// The song is being performed right now
+    pub fn is_playing(&self) -> bool {
+        self.status.as_deref() == Some("playing")
+    }
+
+    pub fn decade(&self) -> Option<i32> {
+        self.year.map(|year| year - year.rem_euclid(10))
+    }
+
+    /// Details shown under the title, "Pop · 1984 · 3:45 · Duo"
+    pub fn details(&self) -> String {
+        let mut details: Vec<String> = vec![];
+        details.extend(self.genre.clone());
+        details.extend(self.language.clone());
+        details.extend(self.year.map(|year| year.to_string()));
+        details.extend(self.duration.map(|seconds| format!("{}:{:02}", seconds / 60, seconds % 60)));
+        if self.duet {
+            details.push("Duo".to_string());
+        }
+        details.extend(self.difficulty.clone());
+        details.join(" · ")
+    }
 }
 
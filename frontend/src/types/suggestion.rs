@@ -0,0 +1,76 @@
+use serde::Deserialize;
+use serde::Serialize;
+use yew::Properties;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Properties)]
+pub struct Suggestion {
+    pub id: i32,
+    pub content: String, // Free text, or a comment next to the artist and title
+    #[serde(default)]
+    pub artist: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub votes: i32, // Guests who asked for the same song, set by the server
+    #[serde(default)]
+    pub status: Option<String>, // "new", "handled", "rejected" or "fulfilled", set by the server
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub proposed_song: Option<ProposedSong>, // Artist and title guessed by the server
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct ProposedSong {
+    pub artist: String,
+    pub title: String,
+}
+
+/// Song asked by the guests that can still get votes, listed by `/requested-songs`
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestedSong {
+    pub id: i32,
+    pub artist: String,
+    pub title: String,
+    pub votes: i32,
+}
+
+/// Song added to the catalog from a suggestion, sent to `/promote-suggestion`
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromoteSuggestion {
+    pub suggestion_id: i32,
+    pub artist: String,
+    pub title: String,
+    pub lyrics_url: String,
+}
+
+/// Suggestions listed by `/suggestions`, with the count of each status
+#[derive(Clone, PartialEq, Deserialize, Default)]
+pub struct SuggestionInbox {
+    pub suggestions: Vec<Suggestion>,
+    pub unread: i64,
+    pub handled: i64,
+    pub rejected: i64,
+    #[serde(default)]
+    pub fulfilled: i64,
+}
+
+impl Suggestion {
+    /// "Artist - Title", None for the free text suggestions
+    pub fn song(&self) -> Option<String> {
+        match (self.artist.is_empty(), self.title.is_empty()) {
+            (_, true) => None,
+            (true, false) => Some(self.title.clone()),
+            (false, false) => Some(format!("{} - {}", self.artist, self.title)),
+        }
+    }
+
+    pub fn status_label(&self) -> &'static str {
+        match self.status.as_deref() {
+            Some("handled") => "Traitée",
+            Some("rejected") => "Refusée",
+            Some("fulfilled") => "Ajoutée au catalogue",
+            _ => "Non lue",
+        }
+    }
+}